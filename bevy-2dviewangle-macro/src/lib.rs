@@ -1,53 +1,395 @@
 use proc_macro::TokenStream;
-use std::collections::HashMap;
 use quote::{
     format_ident,
     quote,
 };
+use std::collections::HashMap;
+use syn::ext::IdentExt;
 use syn::punctuated::Punctuated;
 use syn::{
     Data,
     Expr,
     ExprLit,
     Fields,
+    GenericArgument,
+    Ident,
     Lit,
+    LitInt,
+    LitStr,
     Meta,
+    MetaList,
+    Path,
+    PathArguments,
     Token,
+    Type,
+};
+use xxhash_rust::xxh3::{
+    xxh3_128,
+    xxh3_64,
 };
-use xxhash_rust::xxh3::xxh3_64;
-
-macro_rules! numberize {
-    ($key: expr, $map: expr, $value: expr, $enum_into: expr, $enums: expr) => {{
-        let num;
-        let key_str = $key.value();
-        if $map.contains_key(&key_str) {
-            num = *$map.get(&key_str).unwrap();
-        } else {
-            num = xxh3_64(key_str.as_bytes());
-            $map.insert(key_str, num);
-
-            let enum_name = capitalize_first_letter(&$key.value());
-            let variant_name = syn::Ident::new(&enum_name, $key.span());
-            $enum_into.push(quote! {Self::#variant_name => #num});
-            $enums.push(quote! {#variant_name});
+
+/// Actor or action declared in `textureview` attributes.
+struct Act {
+    name: LitStr,
+    variant: Ident,
+    id: u64,
+}
+
+/// Id of an actor or action, declared the first time its name is seen. Its enum variant is `variant` if given, else
+/// its name in UpperCamelCase.
+fn numberize(key: &LitStr, variant: Option<&LitStr>, acts: &mut Vec<Act>) -> Result<u64, syn::Error> {
+    let name = key.value();
+    if let Some(act) = acts.iter().find(|act| act.name.value() == name) {
+        if let Some(variant) = variant.filter(|variant| act.variant != variant.value()) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                format!("`{}` is already declared with the variant `{}`", name, act.variant),
+            ));
+        }
+        return Ok(act.id);
+    }
+
+    let (variant_name, span) = match variant {
+        Some(variant) => (variant.value(), variant.span()),
+        None => (upper_camel_case(&name), key.span()),
+    };
+    // Parsed rather than built with `Ident::new`, which panics on raw identifiers like `r#Type`
+    let mut ident = syn::parse_str::<Ident>(&variant_name).map_err(|_| {
+        syn::Error::new_spanned(
+            variant.unwrap_or(key),
+            format!(
+                "`{}` is not a valid enum variant name, set one with `variant = \"...\"`",
+                variant_name
+            ),
+        )
+    })?;
+    ident.set_span(span);
+    if const_name(&ident) == "ALL" {
+        return Err(syn::Error::new_spanned(
+            variant.unwrap_or(key),
+            format!(
+                "the variant `{}` gives the constant `ALL`, which is the list of every variant, set another one with \
+                 `variant = \"...\"`",
+                variant_name
+            ),
+        ));
+    }
+    let variant = ident;
+    if let Some(other) = acts
+        .iter()
+        .find(|act| act.variant == variant || const_name(&act.variant) == const_name(&variant))
+    {
+        return Err(syn::Error::new_spanned(
+            key,
+            format!(
+                "`{}` and `{}` give the same enum variant `{}`, set another one with `variant = \"...\"`",
+                other.name.value(),
+                name,
+                variant
+            ),
+        ));
+    }
+
+    let id = xxh3_64(name.as_bytes());
+    acts.push(Act {
+        name: key.clone(),
+        variant,
+        id,
+    });
+    Ok(id)
+}
+
+/// Report different actor or action names with the same id, including the reserved `"any"`, whose sprite sheets would
+/// be merged.
+fn check_id_collisions(actors: &[Act], actions: &[Act], errors: &mut Vec<syn::Error>) {
+    let any_id = xxh3_64(b"any");
+    let mut names: HashMap<u64, String> = HashMap::new();
+    for act in actors.iter().chain(actions) {
+        let name = act.name.value();
+        let existing = names
+            .get(&act.id)
+            .cloned()
+            .or_else(|| (act.id == any_id).then(|| "any".to_string()));
+        match existing {
+            Some(existing) if existing != name => errors.push(syn::Error::new_spanned(
+                &act.name,
+                format!(
+                    "`{}` has the same id {} as `{}`, their sprite sheets would be merged, rename one of them",
+                    name, act.id, existing
+                ),
+            )),
+            _ => {
+                names.insert(act.id, name);
+            }
+        }
+    }
+}
+
+/// Enum variant name of an actor or action: `walk_left`, `walk-left` and `walk left` give `WalkLeft`.
+fn upper_camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars))
+        })
+        .flatten()
+        .collect()
+}
+
+/// Inline grid layout: `grid(tile_width, tile_height, columns, rows)`.
+fn parse_grid(list: &MetaList) -> Result<[u32; 4], syn::Error> {
+    let values = list.parse_args_with(Punctuated::<LitInt, Token![,]>::parse_terminated)?;
+    let values = values
+        .iter()
+        .map(LitInt::base10_parse::<u32>)
+        .collect::<Result<Vec<_>, _>>()?;
+    values
+        .try_into()
+        .map_err(|_| syn::Error::new_spanned(list, "expected `grid(tile_width, tile_height, columns, rows)`"))
+}
+
+/// Id of the layout asset of a grid. The same grids share one layout, whatever the collection.
+fn grid_uuid(grid: &[u32; 4]) -> u128 {
+    xxh3_128(format!("bevy_2dviewangle grid {:?}", grid).as_bytes())
+}
+
+/// Name of the `u64` id constant of an actor or action, from its variant: `WalkLeft` gives `WALK_LEFT`.
+fn const_name(variant: &Ident) -> String {
+    let mut name = String::new();
+    let mut previous_lowercase = false;
+    for c in variant.unraw().to_string().chars() {
+        if c.is_uppercase() && previous_lowercase {
+            name.push('_');
         }
-        $value = quote! {Some(#num)};
-    }};
+        previous_lowercase = c.is_lowercase() || c.is_ascii_digit();
+        name.extend(c.to_uppercase());
+    }
+    name
+}
+
+/// Variant of `Angle` from its name, case-insensitively and ignoring `_`, `-` and spaces like `Angle::from_str`.
+fn angle_variant(name: &str) -> Option<&'static str> {
+    let name: String = name
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    let variant = match name.as_str() {
+        "any" => "Any",
+        "front" => "Front",
+        "back" => "Back",
+        "left" => "Left",
+        "right" => "Right",
+        "frontleft" => "FrontLeft",
+        "frontright" => "FrontRight",
+        "backleft" => "BackLeft",
+        "backright" => "BackRight",
+        _ => return None,
+    };
+    Some(variant)
+}
+
+/// String literal of an attribute value, or an error spanned on the value.
+fn lit_str(value: &Expr) -> Result<&LitStr, syn::Error> {
+    match value {
+        Expr::Lit(ExprLit { lit: Lit::Str(key), .. }) => Ok(key),
+        _ => Err(syn::Error::new_spanned(value, "expected a string literal")),
+    }
+}
+
+/// Kind of asset held by a `textureview` field.
+#[derive(Clone, Copy)]
+enum FieldKind {
+    Image,
+    Layout,
+    /// `Vec<Handle<Image>>`, one image per frame
+    Images,
+    /// `[Handle<Image>; N]`, one image per frame
+    ImageArray,
+}
+
+/// Single generic type argument of the last segment of a path type, if that segment is `ident`.
+fn generic_argument<'a>(ty: &'a Type, ident: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != ident {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.iter().collect::<Vec<_>>()[..] {
+        [GenericArgument::Type(argument)] => Some(argument),
+        _ => None,
+    }
+}
+
+/// Whether the last segment of a path type is `ident`, whatever the module path.
+fn is_type(ty: &Type, ident: &str) -> bool {
+    matches!(ty, Type::Path(path) if path.path.segments.last().is_some_and(|segment| segment.ident == ident))
 }
 
-fn capitalize_first_letter(s: &str) -> String {
-    let mut c = s.chars();
-    match c.next() {
-        None => String::new(),
-        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+fn is_image_handle(ty: &Type) -> bool {
+    generic_argument(ty, "Handle").is_some_and(|asset| is_type(asset, "Image"))
+}
+
+/// Kind of a field from its type, matching on the last path segments so `bevy::prelude::Handle<bevy::image::Image>`
+/// is found too. Type aliases need an explicit `kind`.
+fn field_kind(ty: &Type) -> Option<FieldKind> {
+    if is_image_handle(ty) {
+        return Some(FieldKind::Image);
+    }
+    if generic_argument(ty, "Handle").is_some_and(|asset| is_type(asset, "TextureAtlasLayout")) {
+        return Some(FieldKind::Layout);
+    }
+    if generic_argument(ty, "Vec").is_some_and(is_image_handle) {
+        return Some(FieldKind::Images);
+    }
+    match ty {
+        Type::Array(array) if is_image_handle(&array.elem) => Some(FieldKind::ImageArray),
+        Type::Group(group) => field_kind(&group.elem),
+        Type::Paren(paren) => field_kind(&paren.elem),
+        _ => None,
     }
 }
 
 const TEXTUREVIEW_ATTRIBUTE: &str = "textureview";
 #[proc_macro_derive(View2dCollection, attributes(textureview))]
 pub fn actors_textures_derive(input: TokenStream) -> TokenStream {
-    let ast: syn::DeriveInput = syn::parse(input).unwrap();
-    impl_actors_textures(ast).unwrap_or_default().into()
+    let ast: syn::DeriveInput = match syn::parse(input) {
+        Ok(ast) => ast,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    impl_actors_textures(ast)
+        .unwrap_or_else(|errors| errors.iter().map(syn::Error::to_compile_error).collect())
+        .into()
+}
+
+/// Options of the `textureview` attribute on the struct.
+struct CollectionOptions {
+    /// Path of the `bevy_2dviewangle` crate, for crates which re-export it
+    krate: Path,
+    /// Default path template of the fields
+    path: Option<LitStr>,
+    /// Derive bevy's `Reflect` on the generated enums, which needs `bevy` as a direct dependency
+    reflect: bool,
+}
+
+fn parse_collection_options(ast: &syn::DeriveInput, errors: &mut Vec<syn::Error>) -> CollectionOptions {
+    let mut options = CollectionOptions {
+        krate: syn::parse_quote!(::bevy_2dviewangle),
+        path: None,
+        reflect: false,
+    };
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident(TEXTUREVIEW_ATTRIBUTE))
+    {
+        let view_meta_list = match attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) {
+            Ok(view_meta_list) => view_meta_list,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        for attribute in view_meta_list {
+            match attribute {
+                Meta::NameValue(named_value) if named_value.path.is_ident("crate") => {
+                    match lit_str(&named_value.value).and_then(LitStr::parse) {
+                        Ok(path) => options.krate = path,
+                        Err(e) => errors.push(e),
+                    }
+                }
+                Meta::NameValue(named_value) if named_value.path.is_ident("path") => {
+                    match lit_str(&named_value.value) {
+                        Ok(path) => options.path = Some(path.clone()),
+                        Err(e) => errors.push(e),
+                    }
+                }
+                Meta::Path(path) if path.is_ident("reflect") => options.reflect = true,
+                _ => errors.push(syn::Error::new_spanned(
+                    attribute,
+                    "expected `crate = \"...\"`, `path = \"...\"` or `reflect`",
+                )),
+            }
+        }
+    }
+    options
+}
+
+/// Names a path template of `load_assets` is filled with.
+struct PathNames<'a> {
+    field: &'a Ident,
+    actor: Option<&'a str>,
+    action: Option<&'a str>,
+    angle: &'a str,
+}
+
+/// Fill the placeholders of a path template known at compile time. `{dir}`, `{ext}` and `{frame}` are left to be
+/// filled when loading.
+fn expand_path(template: Option<&LitStr>, names: &PathNames, frames: bool) -> Result<String, syn::Error> {
+    let default = if frames { "{dir}/{field}_{frame}.{ext}" } else { "{dir}/{field}.{ext}" };
+    let text = template.map_or_else(|| default.to_string(), LitStr::value);
+    let error = |message: String| match template {
+        Some(template) => syn::Error::new_spanned(template, message),
+        None => syn::Error::new_spanned(names.field, message),
+    };
+
+    let mut path = String::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        let end = start
+            + rest[start..]
+                .find('}')
+                .ok_or_else(|| error("unclosed `{` in path".to_string()))?;
+        match &rest[start + 1..end] {
+            "field" => path.push_str(&names.field.unraw().to_string()),
+            "actor" => path.push_str(
+                names
+                    .actor
+                    .ok_or_else(|| error("`{actor}` is used in path before any actor is declared".to_string()))?,
+            ),
+            "action" => path.push_str(
+                names
+                    .action
+                    .ok_or_else(|| error("`{action}` is used in path before any action is declared".to_string()))?,
+            ),
+            "angle" => path.push_str(names.angle),
+            "dir" | "ext" => path.push_str(&rest[start..=end]),
+            "frame" if frames => path.push_str(&rest[start..=end]),
+            other => {
+                return Err(error(format!(
+                    "unknown placeholder `{{{}}}` in path, expected `{{dir}}`, `{{ext}}`, `{{field}}`, `{{actor}}`, \
+                     `{{action}}`, `{{angle}}`{}",
+                    other,
+                    if frames { " or `{frame}`" } else { "" }
+                )))
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    path.push_str(rest);
+
+    if frames && !text.contains("{frame}") {
+        return Err(error("path of a frame array needs `{frame}`".to_string()));
+    }
+    Ok(path)
+}
+
+/// Name of an angle in paths, from its variant: `FrontLeft` gives `front_left`.
+fn angle_path_name(variant: &str) -> String {
+    let mut name = String::new();
+    for c in variant.chars() {
+        if c.is_ascii_uppercase() && !name.is_empty() {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
 }
 
 fn impl_actors_textures(ast: syn::DeriveInput) -> Result<proc_macro2::TokenStream, Vec<syn::Error>> {
@@ -55,63 +397,205 @@ fn impl_actors_textures(ast: syn::DeriveInput) -> Result<proc_macro2::TokenStrea
 
     if let Data::Struct(data_struct) = &ast.data {
         if let Fields::Named(fields) = &data_struct.fields {
+            let mut errors = Vec::new();
+            let options = parse_collection_options(&ast, &mut errors);
+            let krate = &options.krate;
+            let mut actor_name = None;
+            let mut action_name = None;
             let mut fields_info = Vec::new();
-            let mut actor_map = HashMap::new();
-            let mut action_map = HashMap::new();
-            let mut actor_nums = Vec::new();
-            let mut action_nums = Vec::new();
-            let mut actor_enums = Vec::new();
-            let mut action_enums = Vec::new();
+            let mut actors = Vec::new();
+            let mut actions = Vec::new();
             let mut field_names = Vec::new();
+            let mut field_paths = Vec::new();
+            let mut image_array_names = Vec::new();
+            let mut image_array_paths = Vec::new();
+            let mut grids = Vec::new();
 
             for field in fields.named.iter() {
                 let field_name = field.ident.as_ref().unwrap();
-                let mut actor_value = quote! {None};
-                let mut action_value = quote! {None};
-                let mut angle_value = quote! {None};
-                let mut image_value = quote! {None};
-                let mut atlas_layout_value = quote! {None};
+                let mut actor_value = quote! {::core::option::Option::None};
+                let mut action_value = quote! {::core::option::Option::None};
+                let mut angle_value = quote! {::core::option::Option::None};
+                let mut image_value = quote! {::core::option::Option::None};
+                let mut atlas_layout_value = quote! {::core::option::Option::None};
+                let mut images_value = quote! {::core::option::Option::None};
+                let mut kind = None;
+                let mut grid = None;
+                let mut path = None;
+                // Fields without angle are registered at `Angle::default()`
+                let mut angle_name = "front".to_string();
 
-                for attr in field
+                let view_attrs: Vec<_> = field
                     .attrs
                     .iter()
                     .filter(|attribute| attribute.path().is_ident(TEXTUREVIEW_ATTRIBUTE))
-                {
-                    let view_meta_list = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated);
-
-                    for attribute in view_meta_list.unwrap() {
-                        match attribute {
-                            Meta::NameValue(named_value) if named_value.path.is_ident("actor") => {
-                                if let Expr::Lit(ExprLit { lit: Lit::Str(key), .. }) = &named_value.value {
-                                    numberize!(key, actor_map, actor_value, actor_nums, actor_enums);
+                    .collect();
+                for attr in &view_attrs {
+                    let view_meta_list = match attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) {
+                        Ok(view_meta_list) => view_meta_list,
+                        Err(e) => {
+                            errors.push(e);
+                            continue;
+                        }
+                    };
+
+                    let mut attr_actor = None;
+                    let mut attr_action = None;
+                    let mut attr_variant = None;
+                    for attribute in view_meta_list {
+                        let named_value = match attribute {
+                            Meta::NameValue(named_value) => named_value,
+                            Meta::List(list) if list.path.is_ident("grid") => {
+                                match parse_grid(&list) {
+                                    Ok(values) => grid = Some((values, list)),
+                                    Err(e) => errors.push(e),
                                 }
+                                continue;
                             }
-                            Meta::NameValue(named_value) if named_value.path.is_ident("action") => {
-                                if let Expr::Lit(ExprLit { lit: Lit::Str(key), .. }) = &named_value.value {
-                                    numberize!(key, action_map, action_value, action_nums, action_enums);
-                                }
+                            _ => {
+                                errors.push(syn::Error::new_spanned(
+                                    attribute,
+                                    "expected `actor = \"...\"`, `action = \"...\"`, `variant = \"...\"`, `angle = \"...\"`, \
+                                     `kind = \"...\"`, `path = \"...\"` or `grid(...)`",
+                                ));
+                                continue;
+                            }
+                        };
+                        let key = match lit_str(&named_value.value) {
+                            Ok(key) => key,
+                            Err(e) => {
+                                errors.push(e);
+                                continue;
                             }
-                            Meta::NameValue(named_value) if named_value.path.is_ident("angle") => {
-                                if let Expr::Lit(ExprLit { lit: Lit::Str(key), .. }) = &named_value.value {
-                                    let key_str = capitalize_first_letter(&key.value());
-                                    let variant_name = syn::Ident::new(&key_str, key.span());
-                                    angle_value = quote! {Some(Angle::#variant_name)};
+                        };
+
+                        if named_value.path.is_ident("actor") {
+                            attr_actor = Some(key.clone());
+                        } else if named_value.path.is_ident("action") {
+                            attr_action = Some(key.clone());
+                        } else if named_value.path.is_ident("variant") {
+                            attr_variant = Some(key.clone());
+                        } else if named_value.path.is_ident("angle") {
+                            match angle_variant(&key.value()) {
+                                Some(variant) => {
+                                    let variant_name = syn::Ident::new(variant, key.span());
+                                    angle_value = quote! {::core::option::Option::Some(#krate::Angle::#variant_name)};
+                                    angle_name = angle_path_name(variant);
                                 }
+                                None => errors.push(syn::Error::new_spanned(
+                                    key,
+                                    format!(
+                                        "unknown angle `{}`, expected one of `any`, `front`, `back`, `left`, `right`, \
+                                         `front_left`, `front_right`, `back_left`, `back_right`",
+                                        key.value()
+                                    ),
+                                )),
                             }
-                            _ => {}
+                        } else if named_value.path.is_ident("kind") {
+                            match key.value().as_str() {
+                                "image" => kind = Some(FieldKind::Image),
+                                "layout" => kind = Some(FieldKind::Layout),
+                                other => errors.push(syn::Error::new_spanned(
+                                    key,
+                                    format!("unknown kind `{}`, expected `image` or `layout`", other),
+                                )),
+                            }
+                        } else if named_value.path.is_ident("path") {
+                            path = Some(key.clone());
+                        } else {
+                            let path = &named_value.path;
+                            errors.push(syn::Error::new_spanned(
+                                path,
+                                format!(
+                                    "unknown key `{}`, expected `actor`, `action`, `variant`, `angle`, `kind` or `path`",
+                                    quote!(#path).to_string().replace(' ', "")
+                                ),
+                            ));
+                        }
+                    }
+
+                    // `variant` names the actor or the action declared in the same attribute
+                    if let Some(variant) = &attr_variant {
+                        if attr_actor.is_some() == attr_action.is_some() {
+                            errors.push(syn::Error::new_spanned(
+                                variant,
+                                "`variant` needs one `actor` or `action` in the same `textureview` attribute",
+                            ));
+                        }
+                    }
+                    if let Some(key) = attr_actor {
+                        let variant = attr_variant.as_ref().filter(|_| attr_action.is_none());
+                        match numberize(&key, variant, &mut actors) {
+                            Ok(id) => actor_value = quote! {::core::option::Option::Some(#id)},
+                            Err(e) => errors.push(e),
                         }
+                        actor_name = Some(key.value());
+                    }
+                    if let Some(key) = attr_action {
+                        match numberize(&key, attr_variant.as_ref(), &mut actions) {
+                            Ok(id) => action_value = quote! {::core::option::Option::Some(#id)},
+                            Err(e) => errors.push(e),
+                        }
+                        action_name = Some(key.value());
                     }
                 }
 
-                match &field.ty {
-                    ty if quote!(#ty).to_string() == "Handle < Image >" => {
-                        field_names.push(field_name);
-                        image_value = quote! {Some(&self.#field_name)}
+                let path_names = PathNames {
+                    field: field_name,
+                    actor: actor_name.as_deref(),
+                    action: action_name.as_deref(),
+                    angle: &angle_name,
+                };
+                let path = path.as_ref().or(options.path.as_ref());
+                match kind.or_else(|| field_kind(&field.ty)) {
+                    Some(FieldKind::Image) => {
+                        match expand_path(path, &path_names, false) {
+                            Ok(path) => {
+                                field_names.push(field_name);
+                                field_paths.push(path);
+                            }
+                            Err(e) => errors.push(e),
+                        }
+                        image_value = quote! {::core::option::Option::Some(&self.#field_name)}
+                    }
+                    Some(FieldKind::Layout) => {
+                        atlas_layout_value = quote! {::core::option::Option::Some(&self.#field_name)}
                     }
-                    ty if quote!(#ty).to_string() == "Handle < TextureAtlasLayout >" => {
-                        atlas_layout_value = quote! {Some(&self.#field_name)}
+                    Some(FieldKind::Images) => {
+                        images_value = quote! {::core::option::Option::Some(&self.#field_name[..])}
+                    }
+                    Some(FieldKind::ImageArray) => {
+                        match expand_path(path, &path_names, true) {
+                            Ok(path) => {
+                                image_array_names.push(field_name);
+                                image_array_paths.push(path);
+                            }
+                            Err(e) => errors.push(e),
+                        }
+                        images_value = quote! {::core::option::Option::Some(&self.#field_name[..])}
+                    }
+                    None if !view_attrs.is_empty() => errors.push(syn::Error::new_spanned(
+                        &field.ty,
+                        "`textureview` field must be a `Handle<Image>`, a `Handle<TextureAtlasLayout>`, a \
+                         `Vec<Handle<Image>>` or a `[Handle<Image>; N]`, or set `kind = \"image\"` or \
+                         `kind = \"layout\"` for a type alias",
+                    )),
+                    None => {}
+                }
+
+                if let Some((values, list)) = grid {
+                    if matches!(kind.or_else(|| field_kind(&field.ty)), Some(FieldKind::Image)) {
+                        let index = grids.iter().position(|grid| *grid == values).unwrap_or_else(|| {
+                            grids.push(values);
+                            grids.len() - 1
+                        });
+                        atlas_layout_value = quote! {::core::option::Option::Some(&GRID_LAYOUTS[#index])};
+                    } else {
+                        errors.push(syn::Error::new_spanned(
+                            list,
+                            "`grid` is only supported on `Handle<Image>` fields",
+                        ));
                     }
-                    _ => {}
                 }
 
                 let field_info = quote! {
@@ -121,59 +605,93 @@ fn impl_actors_textures(ast: syn::DeriveInput) -> Result<proc_macro2::TokenStrea
                         #angle_value,
                         #image_value,
                         #atlas_layout_value,
+                        #images_value,
                     )
                 };
 
                 fields_info.push(field_info);
             }
 
+            check_id_collisions(&actors, &actions, &mut errors);
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+
+            let mut act_names: Vec<String> = actors.iter().chain(&actions).map(|act| act.name.value()).collect();
+            act_names.sort();
+            act_names.dedup();
+
             let actor_enum_name = format_ident!("Actor{}", struct_name);
             let action_enum_name = format_ident!("Action{}", struct_name);
-            let expanded = quote! {
-                use bevy_2dviewangle::*;
+            let grid_count = grids.len();
+            let grid_uuids: Vec<_> = grids.iter().map(grid_uuid).collect();
+            let grid_layouts = grids.iter().map(|[tile_width, tile_height, columns, rows]| {
+                quote! {
+                    #krate::bevy::image::TextureAtlasLayout::from_grid(
+                        #krate::bevy::math::UVec2::new(#tile_width, #tile_height),
+                        #columns,
+                        #rows,
+                        ::core::option::Option::None,
+                        ::core::option::Option::None,
+                    )
+                }
+            });
 
+            let actor_enum = act_enum(krate, options.reflect, &actor_enum_name, &actors);
+            let action_enum = act_enum(krate, options.reflect, &action_enum_name, &actions);
+            let expanded = quote! {
                 #[automatically_derived]
-                impl View2dCollection for #struct_name {
-                    fn get_all(&self) -> Vec<(
-                        Option<u64>,
-                        Option<u64>,
-                        Option<Angle>,
-                        Option<&Handle<Image>>,
-                        Option<&Handle<TextureAtlasLayout>>,
-                    )> {
-                        vec![#( #fields_info ),*]
+                impl #krate::View2dCollection for #struct_name {
+                    fn get_all(&self) -> ::std::vec::Vec<#krate::CollectionField<'_>> {
+                        static GRID_LAYOUTS: [#krate::bevy::asset::Handle<#krate::bevy::image::TextureAtlasLayout>; #grid_count] = [
+                            #( #krate::bevy::asset::Handle::Uuid(
+                                #krate::bevy::asset::uuid::Uuid::from_u128(#grid_uuids),
+                                ::core::marker::PhantomData,
+                            ) ),*
+                        ];
+                        ::std::vec![#( #fields_info ),*]
                     }
-                }
 
-                #[derive(Eq, PartialEq, Clone)]
-                pub enum #actor_enum_name {
-                    #( #actor_enums ),*
-                }
-
-                impl Into<u64> for #actor_enum_name {
-                    fn into(self) -> u64 {
-                        match self {
-                            #( #actor_nums ),*
-                        }
+                    fn act_names(&self) -> ::std::vec::Vec<&'static str> {
+                        ::std::vec![#( #act_names ),*]
                     }
                 }
 
-                #[derive(Eq, PartialEq, Clone)]
-                pub enum #action_enum_name {
-                    #( #action_enums ),*
-                }
+                #actor_enum
 
-                impl Into<u64> for #action_enum_name {
-                    fn into(self) -> u64 {
-                        match self {
-                            #( #action_nums ),*
-                        }
-                    }
-                }
+                #action_enum
 
                 impl #struct_name {
-                    pub fn load_assets(&mut self, asset_dir: &str, extension: &str, asset_server: &AssetServer) {
-                        #( self.#field_names = asset_server.load(format!("{}/{}.{}", asset_dir, stringify!(#field_names), extension)));*
+                    /// Add the layouts declared with `grid(...)` to the assets. Call it before showing the sprites.
+                    pub fn create_layouts(
+                        layouts: &mut #krate::bevy::asset::Assets<#krate::bevy::image::TextureAtlasLayout>,
+                    ) {
+                        #( let _ = layouts.insert(
+                            #krate::bevy::asset::AssetId::Uuid {
+                                uuid: #krate::bevy::asset::uuid::Uuid::from_u128(#grid_uuids),
+                            },
+                            #grid_layouts,
+                        ); )*
+                    }
+
+                    /// Load the images from their path templates, `{dir}/{field}.{ext}` by default.
+                    pub fn load_assets(
+                        &mut self,
+                        asset_dir: &str,
+                        extension: &str,
+                        asset_server: &#krate::bevy::asset::AssetServer,
+                    ) {
+                        #( self.#field_names = asset_server.load(
+                            #field_paths.replace("{dir}", asset_dir).replace("{ext}", extension),
+                        ); )*
+                        #( self.#image_array_names = ::core::array::from_fn(|i| {
+                            asset_server.load(
+                                #image_array_paths
+                                    .replace("{dir}", asset_dir)
+                                    .replace("{ext}", extension)
+                                    .replace("{frame}", &::std::string::ToString::to_string(&i)),
+                            )
+                        }); )*
                     }
                 }
             };
@@ -181,5 +699,333 @@ fn impl_actors_textures(ast: syn::DeriveInput) -> Result<proc_macro2::TokenStrea
         }
     }
 
-    Err(vec![])
+    Err(vec![syn::Error::new_spanned(
+        struct_name,
+        "`View2dCollection` can only be derived for structs with named fields",
+    )])
+}
+
+/// Enum of the actors or actions of a collection, with their names and ids.
+fn act_enum(krate: &Path, reflect: bool, enum_name: &Ident, acts: &[Act]) -> proc_macro2::TokenStream {
+    let variants: Vec<_> = acts.iter().map(|act| &act.variant).collect();
+    let names: Vec<_> = acts.iter().map(|act| &act.name).collect();
+    let ids: Vec<_> = acts.iter().map(|act| act.id).collect();
+    let consts: Vec<_> = acts
+        .iter()
+        .map(|act| Ident::new(&const_name(&act.variant), act.variant.span()))
+        .collect();
+    let count = acts.len();
+
+    // Serialized as the declared names, like `Display`
+    let serde_impl = if cfg!(feature = "serialize") {
+        quote! {
+            impl #krate::serde::Serialize for #enum_name {
+                fn serialize<S: #krate::serde::Serializer>(
+                    &self,
+                    serializer: S,
+                ) -> ::core::result::Result<S::Ok, S::Error> {
+                    serializer.serialize_str(self.name())
+                }
+            }
+
+            impl<'de> #krate::serde::Deserialize<'de> for #enum_name {
+                fn deserialize<D: #krate::serde::Deserializer<'de>>(
+                    deserializer: D,
+                ) -> ::core::result::Result<Self, D::Error> {
+                    let name = <::std::string::String as #krate::serde::Deserialize>::deserialize(deserializer)?;
+                    ::core::str::FromStr::from_str(&name).map_err(<D::Error as #krate::serde::de::Error>::custom)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // `Reflect` finds `bevy` by name in the caller's manifest, whatever `krate` is
+    let reflect_derive = if reflect {
+        quote! { #[derive(#krate::bevy::reflect::Reflect)] }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        #reflect_derive
+        #[derive(
+            ::core::fmt::Debug,
+            ::core::clone::Clone,
+            ::core::marker::Copy,
+            ::core::cmp::Eq,
+            ::core::cmp::PartialEq,
+            ::core::hash::Hash,
+        )]
+        pub enum #enum_name {
+            #( #variants ),*
+        }
+
+        impl #enum_name {
+            /// Every variant, in declaration order.
+            pub const ALL: [Self; #count] = [#( Self::#variants ),*];
+
+            #( pub const #consts: u64 = #ids; )*
+
+            /// Id of the actor or action, as stored in `View2dActor`.
+            pub const fn id(self) -> u64 {
+                match self {
+                    #( Self::#variants => #ids, )*
+                }
+            }
+
+            /// Name of the actor or action, as declared in `textureview`.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    #( Self::#variants => #names, )*
+                }
+            }
+        }
+
+        impl ::core::convert::From<#enum_name> for u64 {
+            fn from(value: #enum_name) -> u64 {
+                value.id()
+            }
+        }
+
+        impl ::core::convert::TryFrom<u64> for #enum_name {
+            type Error = #krate::UnknownActId;
+
+            fn try_from(id: u64) -> ::core::result::Result<Self, Self::Error> {
+                match id {
+                    #( #ids => ::core::result::Result::Ok(Self::#variants), )*
+                    _ => ::core::result::Result::Err(#krate::UnknownActId(id)),
+                }
+            }
+        }
+
+        impl ::core::fmt::Display for #enum_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        impl ::core::str::FromStr for #enum_name {
+            type Err = #krate::ParseActError;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                match s {
+                    #( #names => ::core::result::Result::Ok(Self::#variants), )*
+                    _ => ::core::result::Result::Err(#krate::ParseActError(::std::string::ToString::to_string(s))),
+                }
+            }
+        }
+
+        #serde_impl
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::Span;
+
+    fn names<'a>(field: &'a Ident, actor: Option<&'a str>, action: Option<&'a str>) -> PathNames<'a> {
+        PathNames {
+            field,
+            actor,
+            action,
+            angle: "front_left",
+        }
+    }
+
+    #[test]
+    fn default_path() {
+        let field = Ident::new("idle_front", Span::call_site());
+        let names = names(&field, Some("frog"), Some("idle"));
+        assert_eq!(expand_path(None, &names, false).unwrap(), "{dir}/idle_front.{ext}");
+        assert_eq!(
+            expand_path(None, &names, true).unwrap(),
+            "{dir}/idle_front_{frame}.{ext}"
+        );
+    }
+
+    #[test]
+    fn path_template() {
+        let field = Ident::new_raw("type", Span::call_site());
+        let names = names(&field, Some("frog"), Some("idle"));
+        let template = LitStr::new("{dir}/{actor}/{action}/{angle}_{field}.{ext}", Span::call_site());
+        assert_eq!(
+            expand_path(Some(&template), &names, false).unwrap(),
+            "{dir}/frog/idle/front_left_type.{ext}"
+        );
+    }
+
+    #[test]
+    fn invalid_path_template() {
+        let field = Ident::new("idle", Span::call_site());
+        let template = |text| LitStr::new(text, Span::call_site());
+        let no_actor = names(&field, None, None);
+        assert!(expand_path(Some(&template("{dir}/{actor}.{ext}")), &no_actor, false).is_err());
+
+        let names = names(&field, Some("frog"), Some("idle"));
+        assert!(expand_path(Some(&template("{dir}/{frame}.{ext}")), &names, false).is_err());
+        assert!(expand_path(Some(&template("{dir}/{field}.{ext}")), &names, true).is_err());
+        assert!(expand_path(Some(&template("{dir}/{name}.{ext}")), &names, false).is_err());
+        assert!(expand_path(Some(&template("{dir}/{field.{ext}")), &names, false).is_err());
+    }
+
+    #[test]
+    fn variant_names() {
+        assert_eq!(upper_camel_case("walk_left"), "WalkLeft");
+        assert_eq!(upper_camel_case("walk-left"), "WalkLeft");
+        assert_eq!(upper_camel_case("walk left"), "WalkLeft");
+        assert_eq!(upper_camel_case("Walk_left"), "WalkLeft");
+        assert_eq!(upper_camel_case("attack2"), "Attack2");
+        assert_eq!(upper_camel_case("__idle__"), "Idle");
+    }
+
+    #[test]
+    fn const_names() {
+        let const_name_of = |variant| const_name(&Ident::new(variant, Span::call_site()));
+        assert_eq!(const_name_of("WalkLeft"), "WALK_LEFT");
+        assert_eq!(const_name_of("Attack2Up"), "ATTACK2_UP");
+        assert_eq!(const_name_of("All"), "ALL");
+        assert_eq!(const_name(&Ident::new_raw("type", Span::call_site())), "TYPE");
+    }
+
+    #[test]
+    fn reflect_is_opt_in() {
+        let derives_reflect = |ast| {
+            let tokens = impl_actors_textures(ast).unwrap_or_else(|_| panic!("expected the derive to expand"));
+            tokens.to_string().contains("Reflect")
+        };
+        assert!(!derives_reflect(syn::parse_quote! {
+            #[textureview(crate = "my_engine::view2d")]
+            struct MyAssets {
+                #[textureview(actor = "frog", action = "idle", angle = "front")]
+                idle_front: Handle<Image>,
+            }
+        }));
+        assert!(derives_reflect(syn::parse_quote! {
+            #[textureview(crate = "my_engine::view2d", reflect)]
+            struct MyAssets {
+                #[textureview(actor = "frog", action = "idle", angle = "front")]
+                idle_front: Handle<Image>,
+            }
+        }));
+    }
+
+    /// Message and spanned source of each error of the derive.
+    fn derive_errors(input: &str) -> Vec<(String, String)> {
+        let ast = syn::parse_str(input).unwrap();
+        let Err(errors) = impl_actors_textures(ast) else {
+            panic!("expected the derive to fail");
+        };
+        errors
+            .iter()
+            .map(|error| (error.to_string(), error.span().source_text().unwrap_or_default()))
+            .collect()
+    }
+
+    fn assert_error(input: &str, message: &str, spanned: &str) {
+        let errors = derive_errors(input);
+        assert!(
+            errors
+                .iter()
+                .any(|(error, source)| error.starts_with(message) && source == spanned),
+            "expected `{}` on `{}`, got {:?}",
+            message,
+            spanned,
+            errors
+        );
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert_error(
+            r#"struct MyAssets {
+                #[textureview(actor = "frog", colour = "red")]
+                idle: Handle<Image>,
+            }"#,
+            "unknown key `colour`",
+            "colour",
+        );
+        assert_error(
+            r#"#[textureview(size = "16")]
+            struct MyAssets {}"#,
+            "expected `crate",
+            r#"size = "16""#,
+        );
+    }
+
+    #[test]
+    fn invalid_angle() {
+        assert_error(
+            r#"struct MyAssets {
+                #[textureview(actor = "frog", angle = "up")]
+                idle: Handle<Image>,
+            }"#,
+            "unknown angle `up`",
+            r#""up""#,
+        );
+    }
+
+    #[test]
+    fn non_string_value() {
+        assert_error(
+            r#"struct MyAssets {
+                #[textureview(actor = 3)]
+                idle: Handle<Image>,
+            }"#,
+            "expected a string literal",
+            "3",
+        );
+    }
+
+    #[test]
+    fn unsupported_field_type() {
+        assert_error(
+            r#"struct MyAssets {
+                #[textureview(actor = "frog")]
+                idle: Handle<AudioSource>,
+            }"#,
+            "`textureview` field must be",
+            "Handle<AudioSource>",
+        );
+    }
+
+    #[test]
+    fn only_structs_with_named_fields() {
+        let message = "`View2dCollection` can only be derived for structs with named fields";
+        assert_error("enum MyAssets { Frog }", message, "MyAssets");
+        assert_error("struct MyAssets(Handle<Image>);", message, "MyAssets");
+    }
+
+    #[test]
+    fn raw_variant() {
+        let expanded = impl_actors_textures(syn::parse_quote! {
+            struct MyAssets {
+                #[textureview(actor = "type", variant = "r#Type")]
+                #[textureview(action = "idle", angle = "front")]
+                idle_front: Handle<Image>,
+            }
+        })
+        .unwrap_or_else(|_| panic!("expected the derive to expand"));
+        assert!(expanded.to_string().contains("r#Type"));
+    }
+
+    #[test]
+    fn any_id_collision() {
+        let act = |name: &str, id| Act {
+            name: LitStr::new(name, Span::call_site()),
+            variant: Ident::new("Act", Span::call_site()),
+            id,
+        };
+        let any_id = xxh3_64(b"any");
+        let mut errors = Vec::new();
+        check_id_collisions(&[act("any", any_id)], &[act("any", any_id)], &mut errors);
+        assert!(errors.is_empty());
+
+        // Another name hashed to the id of `"any"`
+        check_id_collisions(&[act("frog", any_id)], &[], &mut errors);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("as `any`"));
+    }
 }
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy::window::WindowResolution;
+use bevy_2dviewangle::{
+    Angle,
+    LoadedSpriteSheets,
+    View2DAnglePluginAnyState,
+    View2dActor,
+    ViewChanged,
+    get_act_id,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()).set(WindowPlugin {
+            primary_window: Some(Window {
+                title: String::from("2D demo with manifest"),
+                resolution: WindowResolution::new(256, 256),
+                ..default()
+            }),
+            ..default()
+        }))
+        // Add the plugin
+        .add_plugins(View2DAnglePluginAnyState::any())
+        .add_systems(Startup, setup)
+        .add_systems(Update, input)
+        .run();
+}
+
+// Keep the manifest handle so it is not unloaded
+#[derive(Resource)]
+struct FrogSheets(#[allow(dead_code)] Handle<LoadedSpriteSheets>);
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // Actors declared in the manifest are registered into `ActorSpriteSheets` once loaded,
+    // then entities of these actors are refreshed automatically
+    commands.insert_resource(FrogSheets(asset_server.load("frog.sheets.ron")));
+
+    commands.spawn(Camera2d);
+    commands.spawn((
+        Transform::from_scale(Vec3::splat(10.)),
+        // Actor and action ids are computed from their names in the manifest
+        View2dActor {
+            actor: get_act_id("frog"),
+            action: get_act_id("idle"),
+            animation_timer: Some(Timer::from_seconds(0.25, TimerMode::Repeating)),
+            ..default()
+        },
+    ));
+}
+
+pub fn input(
+    kb_input: Res<ButtonInput<KeyCode>>,
+    mut actors: Query<(&mut View2dActor, Entity)>,
+    mut action_event: MessageWriter<ViewChanged>,
+) {
+    for (mut act, e) in actors.iter_mut() {
+        let mut direction = act.angle;
+
+        if kb_input.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+            direction = Angle::Left;
+        } else if kb_input.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+            direction = Angle::Right;
+        } else if kb_input.any_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+            direction = Angle::Back;
+        } else if kb_input.any_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+            direction = Angle::Front;
+        }
+
+        if direction != act.angle {
+            act.angle = direction;
+            // Send event to change to sprite sheet to another view
+            action_event.write(ViewChanged { entity: e });
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::component::{
+    Angle,
+    SpriteSheet,
+};
+use crate::loader::{
+    LoadedSpriteSheets,
+    NamedSpriteSheet,
+    split_angle_suffix,
+};
+use bevy::asset::io::Reader;
+use bevy::asset::{
+    AssetLoader,
+    LoadContext,
+    RenderAssetUsages,
+};
+use bevy::image::Image;
+use bevy::math::UVec2;
+use bevy::prelude::TextureAtlasLayout;
+use bevy::reflect::TypePath;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{
+    AnimationDecoder,
+    DynamicImage,
+    Frame,
+    RgbaImage,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::io::Cursor;
+use std::time::Duration;
+
+/// Load an animated GIF (`*.gif`) or APNG (`*.apng`, `*.anim.png`) as one action of an actor.
+///
+/// Frames are packed into one image in a grid, with their durations. The actor, the action and the angle are read
+/// from the file name: `frog_walk_left.gif` is the action `walk` of the actor `frog` at the angle `left`. A file name
+/// without angle is registered at the `any` angle, and a file name without action as the `any` action.
+///
+/// Plain `*.png` files are left to Bevy's image loader, so an APNG named `*.png` must be renamed to `*.anim.png`.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::{Angle, LoadedSpriteSheets};
+/// use bevy_2dviewangle::animated::AnimatedImageLoaderSettings;
+///
+/// fn setup(asset_server: Res<AssetServer>) {
+///     let walk: Handle<LoadedSpriteSheets> = asset_server.load("frog_walk_left.gif");
+///
+///     let idle: Handle<LoadedSpriteSheets> =
+///         asset_server.load_with_settings("idle.apng", |s: &mut AnimatedImageLoaderSettings| {
+///             s.actor = Some("frog".to_string());
+///             s.angle = Some(Angle::Front);
+///         });
+/// }
+/// ```
+#[derive(Default, TypePath)]
+pub struct AnimatedImageLoader;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct AnimatedImageLoaderSettings {
+    /// Actor name. If not set, it's read from the file name.
+    pub actor: Option<String>,
+    /// Action name. If not set, it's read from the file name.
+    pub action: Option<String>,
+    /// Angle of the frames. If not set, it's read from the file name.
+    pub angle: Option<Angle>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnimatedImageLoaderError {
+    #[error("could not read animated image: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not decode animated image: {0}")]
+    Decode(#[from] image::ImageError),
+    #[error("animated image has no frame")]
+    NoFrame,
+}
+
+impl AssetLoader for AnimatedImageLoader {
+    type Asset = LoadedSpriteSheets;
+    type Settings = AnimatedImageLoaderSettings;
+    type Error = AnimatedImageLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let is_gif = load_context.path().path().extension().is_some_and(|ext| ext == "gif");
+        let frames = if is_gif {
+            GifDecoder::new(Cursor::new(&bytes))?.into_frames().collect_frames()?
+        } else {
+            let decoder = PngDecoder::new(Cursor::new(&bytes))?;
+            if decoder.is_apng()? {
+                decoder.apng()?.into_frames().collect_frames()?
+            } else {
+                vec![Frame::new(DynamicImage::from_decoder(decoder)?.into_rgba8())]
+            }
+        };
+
+        let durations = frames
+            .iter()
+            .map(|frame| {
+                let (numerator, denominator) = frame.delay().numer_denom_ms();
+                Duration::from_secs_f64(numerator as f64 / denominator.max(1) as f64 / 1000.0)
+            })
+            .collect();
+        let (image, layout) = pack_frames(frames.into_iter().map(Frame::into_buffer).collect())?;
+        let image = load_context.add_labeled_asset(
+            "image".to_string(),
+            Image::from_dynamic(DynamicImage::ImageRgba8(image), true, RenderAssetUsages::default()),
+        );
+        let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+
+        let stem = load_context
+            .path()
+            .path()
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split('.').next())
+            .unwrap_or_default();
+        let (name, name_angle) = split_angle_suffix(stem);
+        let (name_actor, name_action) = name.rsplit_once('_').unwrap_or((name, "any"));
+        let actor = match (&settings.actor, &settings.action) {
+            (Some(actor), _) => actor.clone(),
+            // The whole name is the actor if the action is given
+            (None, Some(_)) => name.to_string(),
+            (None, None) => name_actor.to_string(),
+        };
+
+        Ok(LoadedSpriteSheets {
+            sheets: vec![NamedSpriteSheet {
+                actor,
+                action: settings.action.clone().unwrap_or_else(|| name_action.to_string()),
+                angle: settings.angle.or(name_angle).unwrap_or(Angle::Any),
+                sheet: SpriteSheet {
+                    image: Some(image),
+                    layout: Some(layout),
+                    durations,
+                    ..Default::default()
+                },
+            }],
+            ..Default::default()
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gif", "apng", "anim.png"]
+    }
+}
+
+/// Pack frames of the same size into a grid, about as wide as it is high.
+fn pack_frames(frames: Vec<RgbaImage>) -> Result<(RgbaImage, TextureAtlasLayout), AnimatedImageLoaderError> {
+    let first = frames.first().ok_or(AnimatedImageLoaderError::NoFrame)?;
+    let tile_size = UVec2::new(first.width(), first.height());
+    let columns = (frames.len() as f64).sqrt().ceil() as u32;
+    let rows = (frames.len() as u32).div_ceil(columns);
+
+    let mut packed = RgbaImage::new(tile_size.x * columns, tile_size.y * rows);
+    for (index, frame) in frames.iter().enumerate() {
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        image::imageops::replace(
+            &mut packed,
+            frame,
+            (column * tile_size.x) as i64,
+            (row * tile_size.y) as i64,
+        );
+    }
+
+    let mut layout = TextureAtlasLayout::from_grid(tile_size, columns, rows, None, None);
+    // The last row may not be full
+    layout.textures.truncate(frames.len());
+    Ok((packed, layout))
+}
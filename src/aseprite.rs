@@ -0,0 +1,404 @@
+use crate::component::{
+    Angle,
+    SpriteSheet,
+};
+use crate::loader::{
+    LoadedSpriteSheets,
+    NamedSpriteSheet,
+    SliceKey,
+    SpriteSlice,
+    split_angle_suffix,
+};
+use bevy::asset::io::Reader;
+use bevy::asset::{
+    AssetLoader,
+    LoadContext,
+};
+use bevy::math::{
+    URect,
+    UVec2,
+};
+use bevy::prelude::TextureAtlasLayout;
+use bevy::reflect::TypePath;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::time::Duration;
+
+/// Load the JSON data exported by Aseprite together with its sprite sheet image (`File > Export Sprite Sheet`).
+///
+/// Each frame tag becomes an action, with the frames and durations of the tag. One file holds one actor at one
+/// angle, both read from the file name: `frog_left.aseprite.json` is the actor `frog` at the angle `left`. A file
+/// name without angle is registered at the `any` angle. In a file whose name and settings have no angle, a tag name
+/// can also end with an angle, such as `idle_left`, to keep every angle in one file. Otherwise tag names are kept
+/// whole. Frames of a file without tag are registered as the `any` action.
+///
+/// Frames must not be trimmed (`Trim Sprite` and `Trim Cels` in the export dialog), as atlas cells can't be offset.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::LoadedSpriteSheets;
+/// use bevy_2dviewangle::aseprite::AsepriteLoaderSettings;
+///
+/// fn setup(asset_server: Res<AssetServer>) {
+///     // Actor and angle from the file name
+///     let frog: Handle<LoadedSpriteSheets> = asset_server.load("frog_left.aseprite.json");
+///
+///     // Or set by loader settings
+///     let frog_front: Handle<LoadedSpriteSheets> =
+///         asset_server.load_with_settings("front.aseprite.json", |s: &mut AsepriteLoaderSettings| {
+///             s.actor = Some("frog".to_string());
+///         });
+/// }
+/// ```
+#[derive(Default, TypePath)]
+pub struct AsepriteLoader;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct AsepriteLoaderSettings {
+    /// Actor name. If not set, it's read from the file name.
+    pub actor: Option<String>,
+    /// Angle of the sprite sheet. If not set, it's read from the file name or the tag name.
+    pub angle: Option<Angle>,
+    /// Action name of the frames if the file has no tag. Default is `any`.
+    pub action: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AsepriteLoaderError {
+    #[error("could not read aseprite file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse aseprite json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid image path `{0}`")]
+    ImagePath(String),
+    #[error("tag `{tag}` refers to frame {frame} but there are only {count} frames")]
+    FrameOutOfRange { tag: String, frame: usize, count: usize },
+    #[error("frame {0} is trimmed, export the sprite sheet without trimming sprites or cels")]
+    TrimmedFrame(usize),
+}
+
+#[derive(Deserialize)]
+struct AsepriteFile {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+/// Aseprite exports frames either as an array or as a map of file names
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AsepriteFrames {
+    Array(Vec<AsepriteFrame>),
+    /// Keeps the order of the file with the `preserve_order` feature of `serde_json`
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    #[serde(default)]
+    duration: u64,
+    #[serde(default)]
+    trimmed: bool,
+    /// Part of the untrimmed cell which `frame` holds
+    sprite_source_size: Option<AsepriteRect>,
+    /// Size of the untrimmed cell
+    source_size: Option<AsepriteSize>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct AsepriteSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct AsepritePoint {
+    x: u32,
+    y: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: String,
+    size: AsepriteSize,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+    #[serde(default)]
+    slices: Vec<AsepriteSlice>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: String,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSlice {
+    name: String,
+    keys: Vec<AsepriteSliceKey>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSliceKey {
+    frame: usize,
+    bounds: AsepriteRect,
+    pivot: Option<AsepritePoint>,
+}
+
+impl From<AsepriteRect> for URect {
+    fn from(rect: AsepriteRect) -> Self {
+        URect::new(rect.x, rect.y, rect.x + rect.w, rect.y + rect.h)
+    }
+}
+
+impl AssetLoader for AsepriteLoader {
+    type Asset = LoadedSpriteSheets;
+    type Settings = AsepriteLoaderSettings;
+    type Error = AsepriteLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: AsepriteFile = serde_json::from_slice(&bytes)?;
+
+        let frames = match file.frames {
+            AsepriteFrames::Array(frames) => frames,
+            AsepriteFrames::Hash(frames) => frames
+                .into_iter()
+                .map(|(_, frame)| serde_json::from_value(frame))
+                .collect::<Result<_, _>>()?,
+        };
+        if let Some(i) = frames.iter().position(AsepriteFrame::is_trimmed) {
+            return Err(AsepriteLoaderError::TrimmedFrame(i));
+        }
+
+        let mut layout = TextureAtlasLayout::new_empty(UVec2::new(file.meta.size.w, file.meta.size.h));
+        for frame in &frames {
+            layout.add_texture(frame.frame.into());
+        }
+        let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+
+        let image_path = load_context
+            .path()
+            .resolve_embed(&file.meta.image)
+            .map_err(|_| AsepriteLoaderError::ImagePath(file.meta.image.clone()))?;
+        let image = load_context.load(image_path);
+
+        let file_name = load_context
+            .path()
+            .path()
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let file_stem = file_name.split('.').next().unwrap_or_default();
+        let (file_actor, file_angle) = split_angle_suffix(file_stem);
+        let actor = settings.actor.clone().unwrap_or_else(|| file_actor.to_string());
+        let angle = settings.angle.or(file_angle);
+
+        let mut tags = file
+            .meta
+            .frame_tags
+            .iter()
+            .map(|tag| {
+                let (action, angle) = tag_action(&tag.name, angle);
+                let indices = tag_frames(tag, frames.len())?;
+                Ok((action.to_string(), angle, indices))
+            })
+            .collect::<Result<Vec<_>, AsepriteLoaderError>>()?;
+        if tags.is_empty() {
+            let action = settings.action.clone().unwrap_or_else(|| "any".to_string());
+            tags.push((action, angle.unwrap_or(Angle::Any), (0..frames.len()).collect()));
+        }
+
+        let sheets = tags
+            .into_iter()
+            .map(|(action, angle, indices)| NamedSpriteSheet {
+                actor: actor.clone(),
+                action,
+                angle,
+                sheet: SpriteSheet {
+                    image: Some(image.clone()),
+                    layout: Some(layout.clone()),
+                    durations: indices
+                        .iter()
+                        .map(|i| Duration::from_millis(frames[*i].duration))
+                        .collect(),
+                    frames: indices,
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        let slices = file
+            .meta
+            .slices
+            .into_iter()
+            .map(|slice| SpriteSlice {
+                name: slice.name,
+                keys: slice
+                    .keys
+                    .into_iter()
+                    .map(|key| SliceKey {
+                        frame: key.frame,
+                        rect: key.bounds.into(),
+                        pivot: key.pivot.map(|pivot| UVec2::new(pivot.x, pivot.y)),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(LoadedSpriteSheets {
+            sheets,
+            slices,
+            ..Default::default()
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json"]
+    }
+}
+
+impl AsepriteFrame {
+    /// Whether the frame is smaller than its cell, which Aseprite may report even if nothing was cut.
+    fn is_trimmed(&self) -> bool {
+        let (Some(part), Some(size)) = (self.sprite_source_size, self.source_size) else {
+            return self.trimmed;
+        };
+        part != (AsepriteRect {
+            x: 0,
+            y: 0,
+            w: size.w,
+            h: size.h,
+        })
+    }
+}
+
+/// Action and angle of a tag. The angle at the end of the tag name is only read if the file has none.
+fn tag_action(name: &str, file_angle: Option<Angle>) -> (&str, Angle) {
+    match file_angle {
+        Some(angle) => (name, angle),
+        None => {
+            let (action, angle) = split_angle_suffix(name);
+            (action, angle.unwrap_or(Angle::Any))
+        }
+    }
+}
+
+/// Atlas indices of a tag in play order, following its direction.
+fn tag_frames(tag: &AsepriteTag, count: usize) -> Result<Vec<usize>, AsepriteLoaderError> {
+    if tag.from > tag.to || tag.to >= count {
+        return Err(AsepriteLoaderError::FrameOutOfRange {
+            tag: tag.name.clone(),
+            frame: tag.to.max(tag.from),
+            count,
+        });
+    }
+
+    let forward: Vec<usize> = (tag.from..=tag.to).collect();
+    let reverse: Vec<usize> = forward.iter().rev().copied().collect();
+    let frames = match tag.direction.as_str() {
+        "reverse" => reverse,
+        // The first and last frames are not repeated when going back
+        "pingpong" => forward
+            .iter()
+            .chain(reverse.iter().skip(1).take(forward.len().saturating_sub(2)))
+            .copied()
+            .collect(),
+        "pingpong_reverse" => reverse
+            .iter()
+            .chain(forward.iter().skip(1).take(forward.len().saturating_sub(2)))
+            .copied()
+            .collect(),
+        _ => forward,
+    };
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(from: usize, to: usize, direction: &str) -> AsepriteTag {
+        AsepriteTag {
+            name: "walk".to_string(),
+            from,
+            to,
+            direction: direction.to_string(),
+        }
+    }
+
+    #[test]
+    fn forward_and_reverse() {
+        assert_eq!(tag_frames(&tag(2, 5, "forward"), 8).unwrap(), [2, 3, 4, 5]);
+        assert_eq!(tag_frames(&tag(2, 5, ""), 8).unwrap(), [2, 3, 4, 5]);
+        assert_eq!(tag_frames(&tag(2, 5, "reverse"), 8).unwrap(), [5, 4, 3, 2]);
+    }
+
+    #[test]
+    fn pingpong_does_not_repeat_ends() {
+        assert_eq!(tag_frames(&tag(0, 3, "pingpong"), 4).unwrap(), [0, 1, 2, 3, 2, 1]);
+        assert_eq!(
+            tag_frames(&tag(0, 3, "pingpong_reverse"), 4).unwrap(),
+            [3, 2, 1, 0, 1, 2]
+        );
+        assert_eq!(tag_frames(&tag(1, 2, "pingpong"), 4).unwrap(), [1, 2]);
+        assert_eq!(tag_frames(&tag(1, 1, "pingpong"), 4).unwrap(), [1]);
+    }
+
+    #[test]
+    fn frames_out_of_range() {
+        assert!(matches!(
+            tag_frames(&tag(2, 4, "forward"), 4),
+            Err(AsepriteLoaderError::FrameOutOfRange { frame: 4, count: 4, .. })
+        ));
+        assert!(tag_frames(&tag(3, 1, "forward"), 4).is_err());
+    }
+
+    #[test]
+    fn tag_angle_only_without_file_angle() {
+        assert_eq!(tag_action("idle_left", None), ("idle", Angle::Left));
+        assert_eq!(tag_action("idle", None), ("idle", Angle::Any));
+        assert_eq!(tag_action("idle_left", Some(Angle::Front)), ("idle_left", Angle::Front));
+    }
+
+    #[test]
+    fn trimmed_frames() {
+        let frame = |trimmed, x, w| -> AsepriteFrame {
+            serde_json::from_value(serde_json::json!({
+                "frame": { "x": 0, "y": 0, "w": w, "h": 16 },
+                "trimmed": trimmed,
+                "spriteSourceSize": { "x": x, "y": 0, "w": w, "h": 16 },
+                "sourceSize": { "w": 16, "h": 16 },
+                "duration": 100,
+            }))
+            .unwrap()
+        };
+        assert!(!frame(false, 0, 16).is_trimmed());
+        // Reported as trimmed, but nothing was cut
+        assert!(!frame(true, 0, 16).is_trimmed());
+        assert!(frame(true, 2, 12).is_trimmed());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::component::ActorSpriteSheets;
+use bevy::asset::{
+    AssetId,
+    Assets,
+};
+use bevy::image::{
+    Image,
+    TextureAtlasBuilder,
+    TextureAtlasBuilderError,
+};
+use bevy::math::{
+    URect,
+    UVec2,
+    UVec3,
+};
+use bevy::platform::collections::{
+    HashMap,
+    HashSet,
+};
+use bevy::prelude::{
+    Resource,
+    TextureAtlasLayout,
+};
+
+/// Actors whose images are packed into one texture atlas once they are loaded.
+///
+/// Every frame of every spritesheet of the actor is copied into one image, and the spritesheets are rewritten to use
+/// it, so the actor is drawn from a single texture.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::{AtlasPacking, get_act_id};
+///
+/// fn setup(mut packing: ResMut<AtlasPacking>) {
+///     packing.pack(get_act_id("frog"));
+/// }
+/// ```
+#[derive(Resource)]
+pub struct AtlasPacking {
+    pending: HashSet<u64>,
+    /// Maximum size of a packed image
+    pub max_size: UVec2,
+    /// Space between frames in a packed image
+    pub padding: UVec2,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AtlasPackingError {
+    #[error("image data of the actor is not available on CPU")]
+    MissingImageData,
+    #[error("layout rect {rect:?} is outside of the image of size {image_size}")]
+    RectOutsideImage { rect: URect, image_size: UVec2 },
+    #[error(transparent)]
+    Builder(#[from] TextureAtlasBuilderError),
+}
+
+impl Default for AtlasPacking {
+    fn default() -> Self {
+        Self {
+            pending: HashSet::new(),
+            max_size: UVec2::splat(4096),
+            padding: UVec2::ZERO,
+        }
+    }
+}
+
+impl AtlasPacking {
+    /// Pack the images of an actor once they are loaded.
+    pub fn pack(&mut self, actor: u64) {
+        self.pending.insert(actor);
+    }
+
+    /// Whether the images of an actor are waiting to be packed.
+    pub fn is_pending(&self, actor: u64) -> bool {
+        self.pending.contains(&actor)
+    }
+
+    pub(crate) fn pending(&self) -> impl Iterator<Item = u64> + '_ {
+        self.pending.iter().copied()
+    }
+
+    pub(crate) fn done(&mut self, actor: u64) {
+        self.pending.remove(&actor);
+    }
+}
+
+impl ActorSpriteSheets {
+    /// Pack every frame of an actor's spritesheets into one image, and make the spritesheets use it.
+    ///
+    /// Returns `Ok(false)` if the actor is not registered yet, or some of its images or layouts are not loaded yet,
+    /// including the layouts `auto_grid` builds.
+    pub fn pack_actor(
+        &mut self,
+        actor: u64,
+        packing: &AtlasPacking,
+        images: &mut Assets<Image>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) -> Result<bool, AtlasPackingError> {
+        type Source = (AssetId<Image>, Option<AssetId<TextureAtlasLayout>>);
+
+        let Some(actions) = self.get(&actor) else {
+            return Ok(false);
+        };
+        if actions
+            .values()
+            .flat_map(|angles| angles.values())
+            .any(|sheet| sheet.auto_grid.is_some() && sheet.layout.is_none())
+        {
+            return Ok(false);
+        }
+
+        let mut sources: Vec<Source> = Vec::new();
+        for sheet in actions.values().flat_map(|angles| angles.values()) {
+            if let Some(image) = &sheet.image {
+                let source = (image.id(), sheet.layout.as_ref().map(|layout| layout.id()));
+                if !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+        }
+
+        let mut frames = Vec::new();
+        let mut offsets = HashMap::new();
+        for (image_id, layout_id) in &sources {
+            let Some(image) = images.get(*image_id) else {
+                return Ok(false);
+            };
+            let rects = match layout_id {
+                Some(layout_id) => match layouts.get(*layout_id) {
+                    Some(layout) => layout.textures.clone(),
+                    None => return Ok(false),
+                },
+                None => vec![URect::from_corners(UVec2::ZERO, image.size())],
+            };
+
+            offsets.insert((*image_id, *layout_id), (frames.len(), rects.len()));
+            for rect in rects {
+                let image_size = image.size();
+                if rect.max.x > image_size.x || rect.max.y > image_size.y {
+                    return Err(AtlasPackingError::RectOutsideImage { rect, image_size });
+                }
+                frames.push(crop_image(image, rect).ok_or(AtlasPackingError::MissingImageData)?);
+            }
+        }
+
+        let mut builder = TextureAtlasBuilder::default();
+        builder.max_size(packing.max_size).padding(packing.padding);
+        for frame in &frames {
+            builder.add_texture(None, frame);
+        }
+        let (layout, _, image) = builder.build()?;
+        let image = images.add(image);
+        let layout = layouts.add(layout);
+
+        let sheets = self
+            .get_mut(&actor)
+            .into_iter()
+            .flat_map(|actions| actions.values_mut());
+        for sheet in sheets.flat_map(|angles| angles.values_mut()) {
+            let Some(source_image) = &sheet.image else {
+                continue;
+            };
+            let source = (source_image.id(), sheet.layout.as_ref().map(|layout| layout.id()));
+            let Some((offset, count)) = offsets.get(&source) else {
+                continue;
+            };
+
+            sheet.frames = if sheet.frames.is_empty() {
+                (*offset..offset + count).collect()
+            } else {
+                sheet.frames.iter().map(|frame| offset + frame).collect()
+            };
+            sheet.image = Some(image.clone());
+            sheet.layout = Some(layout.clone());
+        }
+        self.mark_changed(actor);
+
+        Ok(true)
+    }
+}
+
+/// New transparent image with the format and sampler of `like`.
+pub(crate) fn blank_image(like: &Image, size: UVec2) -> Image {
+    let pixel_size = like.pixel_bytes(UVec3::ZERO).map_or(0, <[u8]>::len);
+    let mut image = Image {
+        texture_descriptor: like.texture_descriptor.clone(),
+        sampler: like.sampler.clone(),
+        texture_view_descriptor: like.texture_view_descriptor.clone(),
+        asset_usage: like.asset_usage,
+        ..Default::default()
+    };
+    image.texture_descriptor.size.width = size.x;
+    image.texture_descriptor.size.height = size.y;
+    image.data = Some(vec![0; (size.x * size.y) as usize * pixel_size]);
+    image
+}
+
+/// Copy a region of an image into a new image.
+pub(crate) fn crop_image(source: &Image, rect: URect) -> Option<Image> {
+    let mut image = blank_image(source, rect.size());
+    for y in 0..rect.height() {
+        for x in 0..rect.width() {
+            let pixel = source.pixel_bytes(UVec3::new(rect.min.x + x, rect.min.y + y, 0))?;
+            image.pixel_bytes_mut(UVec3::new(x, y, 0))?.copy_from_slice(pixel);
+        }
+    }
+    Some(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{
+        Angle,
+        AutoGrid,
+        SpriteSheet,
+    };
+
+    #[test]
+    fn pack_auto_grid_after_it_is_built() {
+        let mut images = Assets::default();
+        let mut layouts = Assets::default();
+        let mut animation2d = ActorSpriteSheets::default();
+        let sheet = SpriteSheet {
+            image: Some(images.add(blank_image(&Image::default(), UVec2::new(32, 16)))),
+            auto_grid: Some(AutoGrid::Frames(2)),
+            ..Default::default()
+        };
+        animation2d.add_sprite_sheet(1, 2, Angle::Front, sheet);
+
+        let packing = AtlasPacking::default();
+        assert!(!animation2d.pack_actor(1, &packing, &mut images, &mut layouts).unwrap());
+
+        animation2d.build_auto_grids(&images, &mut layouts);
+        assert!(animation2d.pack_actor(1, &packing, &mut images, &mut layouts).unwrap());
+        let (sheet, _) = animation2d.get_sprite_sheet(1, 2, Angle::Front).unwrap();
+        assert_eq!(sheet.frames.len(), 2);
+        let layout = layouts.get(sheet.layout.as_ref().unwrap()).unwrap();
+        assert!(
+            sheet
+                .frames
+                .iter()
+                .all(|frame| layout.textures[*frame].size() == UVec2::new(16, 16))
+        );
+    }
+
+    #[test]
+    fn unregistered_actor_stays_pending() {
+        let packing = AtlasPacking::default();
+        let result =
+            ActorSpriteSheets::default().pack_actor(1, &packing, &mut Assets::default(), &mut Assets::default());
+        assert!(!result.unwrap());
+    }
+}
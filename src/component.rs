@@ -1,5 +1,22 @@
-use bevy::asset::Handle;
-use bevy::platform::collections::HashMap;
+use crate::names::{
+    ActName,
+    ViewNames,
+};
+use bevy::asset::{
+    Assets,
+    Handle,
+    UntypedAssetId,
+    VisitAssetDependencies,
+};
+use bevy::log::{
+    error,
+    warn,
+};
+use bevy::math::UVec2;
+use bevy::platform::collections::{
+    HashMap,
+    HashSet,
+};
 use bevy::prelude::{
     Component,
     Deref,
@@ -16,6 +33,10 @@ use bevy::prelude::{
 };
 use bevy::sprite::Sprite;
 pub use bevy_2dviewangle_macro::View2dCollection;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use xxhash_rust::const_xxh3;
 use xxhash_rust::xxh3::xxh3_64;
 
 /// The trait to use in derive macro. You won't need to implement this trait.
@@ -41,33 +62,66 @@ use xxhash_rust::xxh3::xxh3_64;
 ///     // If angle is any, other angle which has not been defined will use this value
 ///     #[textureview(angle = "any")]
 ///     pub layout: Handle<TextureAtlasLayout>,
+///
+///     // One image per frame, played in order instead of atlas cells
+///     #[textureview(action = "walk", angle = "front")]
+///     pub walk_front: [Handle<Image>; 4],
+///
+///     #[textureview(angle = "back")]
+///     pub walk_back: Vec<Handle<Image>>,
+///
+///     // Layout declared inline, added to the assets by `MyAssets::create_layouts`
+///     #[textureview(action = "jump", angle = "any", grid(16, 16, 1, 3))]
+///     pub jump: Handle<Image>,
 /// }
 /// ```
 ///
-/// Two enums will be generated base on declared actor and action:
-/// ```rust
-/// #[derive(Eq, PartialEq, Clone)]
+/// Two enums will be generated base on declared actor and action. `Reflect` needs `bevy` to be a direct dependency of
+/// the crate using the derive:
+/// ```rust,ignore
+/// #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Reflect)]
 /// pub enum ActorMyAssets {
 ///     Frog,
 /// }
 ///
-/// #[derive(Eq, PartialEq, Clone)]
+/// #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Reflect)]
 /// pub enum ActionMyAssets {
 ///     Idle,
+///     Walk,
+/// }
+/// ```
+///
+/// They convert from and to ids with `From`/`TryFrom<u64>`, and from and to the declared names with
+/// `Display`/`FromStr` (and serde with the `serialize` feature). `ALL` lists the variants, and the ids are also
+/// constants to match on:
+/// ```rust,ignore
+/// match actor.action {
+///     ActionMyAssets::IDLE => {}
+///     ActionMyAssets::WALK => {}
+///     _ => {}
 /// }
 /// ```
 pub trait View2dCollection {
-    fn get_all(
-        &self,
-    ) -> Vec<(
-        Option<u64>,
-        Option<u64>,
-        Option<Angle>,
-        Option<&Handle<Image>>,
-        Option<&Handle<TextureAtlasLayout>>,
-    )>;
+    fn get_all(&self) -> Vec<CollectionField<'_>>;
+
+    /// Names of the declared actors and actions, to map their ids back to names.
+    fn act_names(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
 }
 
+/// One field of a `View2dCollection`: actor, action, angle, image, layout and per-frame images.
+///
+/// Actor and action are `None` when the field uses the ones of the previous field.
+pub type CollectionField<'a> = (
+    Option<u64>,
+    Option<u64>,
+    Option<Angle>,
+    Option<&'a Handle<Image>>,
+    Option<&'a Handle<TextureAtlasLayout>>,
+    Option<&'a [Handle<Image>]>,
+);
+
 /// All supported angles.
 #[cfg_attr(feature = "serialize", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Reflect, Default, Clone, Copy, Eq, PartialEq, Hash, Debug)]
@@ -89,6 +143,41 @@ pub enum Angle {
 pub struct SpriteSheet {
     pub layout: Option<Handle<TextureAtlasLayout>>,
     pub image: Option<Handle<Image>>,
+    /// Atlas indices of the animation frames, in play order. Empty means every frame of the layout.
+    pub frames: Vec<usize>,
+    /// Duration of each animation frame. Empty means the actor's `animation_timer` is used as is.
+    pub durations: Vec<Duration>,
+    /// Grid to build the layout from once the image is loaded, if `layout` is not set
+    pub auto_grid: Option<AutoGrid>,
+    /// One image per frame, shown instead of `image` and `layout`. `frames` are indices in this list if set.
+    pub images: Vec<Handle<Image>>,
+}
+
+/// Parameters of `TextureAtlasLayout::from_grid`
+#[cfg_attr(feature = "serialize", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct GridLayout {
+    pub tile_size: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub padding: Option<UVec2>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub offset: Option<UVec2>,
+}
+
+/// Grid layout which is built from the size of the image once it is loaded
+#[cfg_attr(feature = "serialize", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AutoGrid {
+    /// Cells of this size, as many as fit in the image
+    CellSize(UVec2),
+    /// Image split into columns and rows
+    Cells { columns: u32, rows: u32 },
+    /// Image split into frames along its longer side
+    Frames(u32),
+    /// Columns and rows read from the image file name, such as `walk_8x1.png`
+    FromName,
 }
 
 /// Map of Angle and its SpriteSheet
@@ -96,14 +185,14 @@ pub struct SpriteSheet {
 pub struct AngleSpriteSheets(HashMap<Angle, SpriteSheet>);
 
 #[cfg_attr(feature = "serialize", derive(serde::Deserialize, serde::Serialize))]
-#[derive(Reflect, Clone)]
+#[derive(Reflect, Clone, Debug)]
 pub enum Notification {
     LastFrame,
 }
 
 #[cfg_attr(feature = "serialize", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Component, Reflect, Default, Clone)]
-#[reflect(Component)]
+#[reflect(Component, Debug)]
 #[require(Sprite)]
 pub struct View2dActor {
     pub angle: Angle,
@@ -112,13 +201,93 @@ pub struct View2dActor {
     pub next_action: Vec<u64>,
     pub actor: u64,
     pub flipped: bool,
+    /// Current position in the animation frames of the sprite sheet
+    #[cfg_attr(feature = "serialize", serde(default))]
+    #[reflect(default)]
+    pub frame: usize,
+    /// Level of detail, set from the distance to the `LodCamera`. 0 is the most detailed.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    #[reflect(default)]
+    pub lod: usize,
     pub animation_timer: Option<Timer>,
     pub notify: Vec<Notification>,
+    /// Names of `actor` and `action`, set by the plugin
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    #[reflect(default)]
+    pub names: ViewNames,
+}
+
+impl fmt::Debug for View2dActor {
+    /// Actor and action are shown with their names once the plugin has set them.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("View2dActor")
+            .field("angle", &self.angle)
+            .field(
+                "action",
+                &ActName {
+                    id: self.action,
+                    name: self.names.action.as_deref(),
+                },
+            )
+            .field("next_action", &self.next_action)
+            .field(
+                "actor",
+                &ActName {
+                    id: self.actor,
+                    name: self.names.actor.as_deref(),
+                },
+            )
+            .field("flipped", &self.flipped)
+            .field("frame", &self.frame)
+            .field("lod", &self.lod)
+            .field("animation_timer", &self.animation_timer)
+            .field("notify", &self.notify)
+            .finish()
+    }
 }
 
 /// The resource that stores every spritesheets. Organized by actor id and action id.
+///
+/// Entities of an actor are refreshed when the actor's spritesheets change. Changes made by the methods of this
+/// resource only refresh the affected actors. Changes made on the map directly refresh every actor, unless
+/// `mark_changed` is called.
 #[derive(Resource, Deref, DerefMut, Default)]
-pub struct ActorSpriteSheets(HashMap<u64, HashMap<u64, AngleSpriteSheets>>);
+pub struct ActorSpriteSheets {
+    #[deref]
+    sheets: HashMap<u64, HashMap<u64, AngleSpriteSheets>>,
+    changed: HashSet<u64>,
+    // Removed actors, with `None`, and removed actions, until they are added again
+    removed: HashSet<(u64, Option<u64>)>,
+    names: HashMap<u64, String>,
+    parents: HashMap<u64, u64>,
+    lods: HashMap<(u64, u64), Vec<LodLevel>>,
+}
+
+/// Spritesheets of an action used from a distance to the camera
+#[derive(Default)]
+pub struct LodLevel {
+    pub min_distance: f32,
+    pub sheets: AngleSpriteSheets,
+}
+
+/// Camera from which the distance of actors is measured to pick their level of detail
+#[derive(Component, Default)]
+pub struct LodCamera;
+
+/// What happens to entities whose actor or action is removed from `ActorSpriteSheets` while they show it.
+///
+/// Entities which never showed a spritesheet, such as ones spawned before their actor is registered, and entities
+/// whose spritesheet is just not found, are left as is.
+#[derive(Resource, Default, Clone)]
+pub enum RemovedSheetPolicy {
+    /// Log a warning and clear the sprite, so the removed assets can be freed
+    #[default]
+    Warn,
+    /// Show this image instead
+    Placeholder(Handle<Image>),
+    /// Despawn the entity
+    Despawn,
+}
 
 /// Notify the view is changed.
 ///
@@ -145,6 +314,12 @@ pub struct ViewChanged {
     pub entity: Entity,
 }
 
+/// Sent when every image and layout of an actor is loaded, and again if its sprite sheets are changed and loaded.
+#[derive(Message)]
+pub struct ActorSheetsLoaded {
+    pub actor: u64,
+}
+
 /// Sent when animation went to the last frame
 #[derive(EntityEvent)]
 pub struct LastFrame {
@@ -157,6 +332,211 @@ pub struct NextFrame {
     pub entity: Entity,
 }
 
+impl Angle {
+    /// Every angle, `Any` first.
+    pub const ALL: [Angle; 9] = [
+        Angle::Any,
+        Angle::Front,
+        Angle::Back,
+        Angle::Left,
+        Angle::Right,
+        Angle::FrontLeft,
+        Angle::FrontRight,
+        Angle::BackLeft,
+        Angle::BackRight,
+    ];
+
+    /// The mirrored angle, which can be flipped to show this angle.
+    pub fn opposite(&self) -> Option<Angle> {
+        match self {
+            Angle::Left => Some(Angle::Right),
+            Angle::Right => Some(Angle::Left),
+            Angle::FrontLeft => Some(Angle::FrontRight),
+            Angle::FrontRight => Some(Angle::FrontLeft),
+            Angle::BackLeft => Some(Angle::BackRight),
+            Angle::BackRight => Some(Angle::BackLeft),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Angle {
+    type Err = ParseAngleError;
+
+    /// Parse angle name case-insensitively, ignoring `_`, `-` and spaces. So `front_left`, `FrontLeft` and
+    /// `front-left` are the same angle.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, '_' | '-' | ' '))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        match name.as_str() {
+            "any" => Ok(Angle::Any),
+            "front" => Ok(Angle::Front),
+            "back" => Ok(Angle::Back),
+            "left" => Ok(Angle::Left),
+            "right" => Ok(Angle::Right),
+            "frontleft" => Ok(Angle::FrontLeft),
+            "frontright" => Ok(Angle::FrontRight),
+            "backleft" => Ok(Angle::BackLeft),
+            "backright" => Ok(Angle::BackRight),
+            _ => Err(ParseAngleError(s.to_string())),
+        }
+    }
+}
+
+/// Error when parsing an unknown angle name
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[error("unknown angle `{0}`")]
+pub struct ParseAngleError(pub String);
+
+/// Two different actor or action names with the same id, whose sprite sheets would be merged
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[error("`{name}` has the same id {id} as `{existing}`")]
+pub struct ActIdCollision {
+    pub id: u64,
+    /// Name registered first
+    pub existing: String,
+    pub name: String,
+}
+
+/// Error when converting an id which is not declared into a generated actor or action enum
+#[derive(Debug, Clone, Copy, Eq, PartialEq, thiserror::Error)]
+#[error("unknown actor or action id {0}")]
+pub struct UnknownActId(pub u64);
+
+/// Error when parsing a name which is not declared into a generated actor or action enum
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[error("unknown actor or action `{0}`")]
+pub struct ParseActError(pub String);
+
+impl SpriteSheet {
+    /// Number of animation frames, given the atlas layout of this sprite sheet.
+    pub fn frame_count(&self, layout: &TextureAtlasLayout) -> usize {
+        if self.frames.is_empty() { layout.textures.len() } else { self.frames.len() }
+    }
+
+    /// Atlas index of an animation frame.
+    pub fn atlas_index(&self, frame: usize) -> usize {
+        self.frames.get(frame).copied().unwrap_or(frame)
+    }
+
+    /// Number of animation frames of a sprite sheet with one image per frame.
+    pub fn image_frame_count(&self) -> usize {
+        if self.frames.is_empty() { self.images.len() } else { self.frames.len() }
+    }
+
+    /// Image of an animation frame, for a sprite sheet with one image per frame.
+    pub fn image_at(&self, frame: usize) -> Option<&Handle<Image>> {
+        self.images.get(self.atlas_index(frame))
+    }
+
+    fn fill_from(&mut self, other: &SpriteSheet) {
+        if self.images.is_empty() && self.image.is_none() {
+            self.images.clone_from(&other.images);
+        }
+        if self.image.is_none() {
+            self.image.clone_from(&other.image);
+        }
+        if self.layout.is_none() && self.auto_grid.is_none() {
+            self.layout.clone_from(&other.layout);
+            self.auto_grid = other.auto_grid;
+        }
+        if self.frames.is_empty() {
+            self.frames.clone_from(&other.frames);
+        }
+        if self.durations.is_empty() {
+            self.durations.clone_from(&other.durations);
+        }
+    }
+}
+
+impl VisitAssetDependencies for SpriteSheet {
+    fn visit_dependencies(&self, visit: &mut impl FnMut(UntypedAssetId)) {
+        self.image.visit_dependencies(visit);
+        self.layout.visit_dependencies(visit);
+        self.images.visit_dependencies(visit);
+    }
+}
+
+impl GridLayout {
+    /// Build the atlas layout of this grid.
+    pub fn to_layout(&self) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(self.tile_size, self.columns, self.rows, self.padding, self.offset)
+    }
+
+    /// Find the grid which builds this atlas layout, if it is a grid.
+    pub fn from_layout(layout: &TextureAtlasLayout) -> Option<GridLayout> {
+        let first = layout.textures.first()?;
+        let columns = layout
+            .textures
+            .iter()
+            .take_while(|rect| rect.min.y == first.min.y)
+            .count() as u32;
+        let rows = layout.textures.len() as u32 / columns;
+        let padding = UVec2::new(
+            layout
+                .textures
+                .get(1)
+                .filter(|_| columns > 1)
+                .map_or(0, |rect| rect.min.x.saturating_sub(first.max.x)),
+            layout
+                .textures
+                .get(columns as usize)
+                .map_or(0, |rect| rect.min.y.saturating_sub(first.max.y)),
+        );
+        let grid = GridLayout {
+            tile_size: first.size(),
+            columns,
+            rows,
+            padding: Some(padding).filter(|padding| *padding != UVec2::ZERO),
+            offset: Some(first.min).filter(|offset| *offset != UVec2::ZERO),
+        };
+        (grid.to_layout() == *layout).then_some(grid)
+    }
+}
+
+impl AutoGrid {
+    /// Grid of an image of `size`, with the file name `name` for `AutoGrid::FromName`.
+    pub fn to_grid(&self, size: UVec2, name: &str) -> Option<GridLayout> {
+        let (columns, rows) = match *self {
+            AutoGrid::CellSize(cell) if cell.x > 0 && cell.y > 0 => (size.x / cell.x, size.y / cell.y),
+            AutoGrid::CellSize(_) => return None,
+            AutoGrid::Cells { columns, rows } => (columns, rows),
+            AutoGrid::Frames(frames) if size.y > size.x => (1, frames),
+            AutoGrid::Frames(frames) => (frames, 1),
+            AutoGrid::FromName => split_grid_suffix(name).1?,
+        };
+        if columns == 0 || rows == 0 {
+            return None;
+        }
+        let tile_size = match *self {
+            // The image may have a margin which is not a whole cell
+            AutoGrid::CellSize(cell) => cell,
+            _ => size / UVec2::new(columns, rows),
+        };
+        Some(GridLayout {
+            tile_size,
+            columns,
+            rows,
+            ..Default::default()
+        })
+    }
+}
+
+/// Split the columns and rows suffix of a file stem, so `walk_8x1` is `("walk", Some((8, 1)))`.
+pub fn split_grid_suffix(stem: &str) -> (&str, Option<(u32, u32)>) {
+    let grid = stem.rsplit_once(['_', '-']).and_then(|(base, suffix)| {
+        let (columns, rows) = suffix.split_once('x')?;
+        Some((base, (columns.parse().ok()?, rows.parse().ok()?)))
+    });
+    match grid {
+        Some((base, grid)) => (base, Some(grid)),
+        None => (stem, None),
+    }
+}
+
 impl AngleSpriteSheets {
     /// Store spritesheets from list of Angle and SpriteSheet in case you don't want to use derive `View2dCollection`.
     pub fn from(items: Vec<(Angle, SpriteSheet)>) -> Self {
@@ -210,66 +590,507 @@ impl ActorSpriteSheets {
     /// }
     /// ```
     pub fn load_asset_loader<T: View2dCollection>(&mut self, loader: &T) {
+        for name in loader.act_names() {
+            self.register_name(name);
+        }
+
         let mut actor_id = 0;
         let mut action_id = 0;
+        let mut fallback = false;
 
-        for (actor, action, angle, image, atlas_layout) in loader.get_all() {
+        for (actor, action, angle, image, atlas_layout, images) in loader.get_all() {
             actor_id = actor.unwrap_or(actor_id);
             action_id = action.unwrap_or(action_id);
-            let field_angle = angle.unwrap_or_default();
-            let actor;
-            if let Some(_actor) = self.get_mut(&actor_id) {
-                actor = _actor;
-            } else {
-                self.insert(actor_id, HashMap::default());
-                actor = self.get_mut(&actor_id).unwrap();
-            }
+            fallback |= actor_id == ANY_ID;
+            let sheet = SpriteSheet {
+                image: image.cloned(),
+                layout: atlas_layout.cloned(),
+                images: images.map(<[_]>::to_vec).unwrap_or_default(),
+                ..Default::default()
+            };
+            self.add_sprite_sheet(actor_id, action_id, angle.unwrap_or_default(), sheet);
+        }
+        if fallback {
+            warn_fallback_actor();
+        }
+    }
+
+    /// Store one spritesheet of an actor's action.
+    ///
+    /// Fields which are set in `sheet` replace the stored ones. Fields which are not set are taken from the `Any`
+    /// angle of the same action. If `angle` is `Any`, it fills the missing fields of the other angles instead.
+    pub fn add_sprite_sheet(&mut self, actor: u64, action: u64, angle: Angle, sheet: SpriteSheet) {
+        self.changed.insert(actor);
+        self.removed.remove(&(actor, None));
+        self.removed.remove(&(actor, Some(action)));
+        let action = self.sheets.entry(actor).or_default().entry(action).or_default();
+        let any = action.get(&Angle::Any).cloned();
+        let sprite = action.entry(angle).or_default();
+
+        if sheet.image.is_some() {
+            sprite.image = sheet.image;
+        }
+        if sheet.layout.is_some() {
+            sprite.layout = sheet.layout;
+        }
+        if !sheet.frames.is_empty() {
+            sprite.frames = sheet.frames;
+        }
+        if !sheet.durations.is_empty() {
+            sprite.durations = sheet.durations;
+        }
+        if sheet.auto_grid.is_some() {
+            sprite.auto_grid = sheet.auto_grid;
+        }
+        if !sheet.images.is_empty() {
+            sprite.images = sheet.images;
+        }
 
-            let action;
-            if let Some(_action) = actor.get_mut(&action_id) {
-                action = _action;
-            } else {
-                actor.insert(action_id, AngleSpriteSheets::default());
-                action = actor.get_mut(&action_id).unwrap();
+        if angle == Angle::Any {
+            let any = sprite.clone();
+            for s in action.values_mut() {
+                s.fill_from(&any);
             }
+        } else if let Some(any) = any.as_ref() {
+            sprite.fill_from(any);
+        }
+    }
 
-            let any = action.get(&Angle::Any).cloned();
-            let sprite;
-            if let Some(_sprite) = action.get_mut(&field_angle) {
-                sprite = _sprite;
-            } else {
-                action.insert(field_angle, SpriteSheet::default());
-                sprite = action.get_mut(&field_angle).unwrap();
+    /// Remove every spritesheet of an actor, dropping its handles so the assets can be freed. Its levels of detail
+    /// and its parent are removed too, so they don't come back if the actor is added again. Actors which inherit from
+    /// it keep their parent.
+    ///
+    /// Entities which show the actor are handled by `RemovedSheetPolicy`.
+    pub fn remove_actor(&mut self, actor: u64) -> Option<HashMap<u64, AngleSpriteSheets>> {
+        let lods = self.lods.len();
+        self.lods.retain(|(lod_actor, _), _| *lod_actor != actor);
+        let parent = self.parents.remove(&actor);
+        let removed = self.sheets.remove(&actor);
+        if removed.is_some() || parent.is_some() || lods != self.lods.len() {
+            self.changed.insert(actor);
+            self.removed.insert((actor, None));
+        }
+        removed
+    }
+
+    /// Remove the spritesheets and levels of detail of an actor's action. The actor is removed too if it has no action
+    /// left.
+    pub fn remove_action(&mut self, actor: u64, action: u64) -> Option<AngleSpriteSheets> {
+        if self.lods.remove(&(actor, action)).is_some() {
+            self.changed.insert(actor);
+        }
+        let actions = self.sheets.get_mut(&actor)?;
+        let removed = actions.remove(&action)?;
+        if actions.is_empty() {
+            self.sheets.remove(&actor);
+        }
+        self.changed.insert(actor);
+        self.removed.insert((actor, Some(action)));
+        Some(removed)
+    }
+
+    /// Remove the spritesheet of an actor's action at an angle. The action is removed too if it has no angle left.
+    ///
+    /// Other angles keep the fields they took from the `Any` angle.
+    pub fn remove_angle(&mut self, actor: u64, action: u64, angle: Angle) -> Option<SpriteSheet> {
+        let sheets = self.sheets.get_mut(&actor)?.get_mut(&action)?;
+        let removed = sheets.remove(&angle)?;
+        if sheets.is_empty() {
+            self.remove_action(actor, action);
+        }
+        self.changed.insert(actor);
+        Some(removed)
+    }
+
+    /// Build the layouts of spritesheets which have an `auto_grid` and no layout, once their image is loaded.
+    ///
+    /// Returns the actors whose layouts are built. Their entities are not refreshed, call `mark_changed` for that.
+    pub fn build_auto_grids(
+        &mut self,
+        images: &Assets<Image>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) -> HashSet<u64> {
+        let mut built = HashSet::new();
+        for (&actor, actions) in self.sheets.iter_mut() {
+            for sheet in actions.values_mut().flat_map(|angles| angles.values_mut()) {
+                if sheet.layout.is_some() {
+                    continue;
+                }
+                let (Some(auto_grid), Some(handle)) = (sheet.auto_grid, sheet.image.as_ref()) else {
+                    continue;
+                };
+                let Some(image) = images.get(handle) else {
+                    continue;
+                };
+                let name = handle
+                    .path()
+                    .and_then(|path| path.path().file_stem()?.to_str())
+                    .unwrap_or_default();
+                if let Some(grid) = auto_grid.to_grid(image.size(), name) {
+                    sheet.layout = Some(layouts.add(grid.to_layout()));
+                    built.insert(actor);
+                }
             }
+        }
+        built
+    }
 
-            if let Some(image_handle) = image {
-                sprite.image = Some(image_handle.clone());
-            } else if let Some(any) = any.as_ref() {
-                sprite.image.clone_from(&any.image);
+    /// Remember the name of an actor or action id, and return the id. A name whose id is already taken by another
+    /// name is logged as an error, and their sprite sheets are merged.
+    pub fn register_name(&mut self, name: &str) -> u64 {
+        match self.try_register_name(name) {
+            Ok(id) => id,
+            Err(e) => {
+                error!("{e}, their sprite sheets are merged");
+                e.id
             }
+        }
+    }
 
-            if let Some(atlas_layout_handle) = atlas_layout {
-                sprite.layout = Some(atlas_layout_handle.clone());
-            } else if let Some(any) = any.as_ref() {
-                sprite.layout.clone_from(&any.layout);
+    /// Remember the name of an actor or action id, and return the id. Fails if another registered name, or the
+    /// reserved `"any"`, has the same id.
+    pub fn try_register_name(&mut self, name: &str) -> Result<u64, ActIdCollision> {
+        let id = get_act_id(name);
+        let reserved = (id == ANY_ID).then_some("any");
+        match self.names.get(&id).map(String::as_str).or(reserved) {
+            Some(existing) if existing != name => Err(ActIdCollision {
+                id,
+                existing: existing.to_string(),
+                name: name.to_string(),
+            }),
+            _ => {
+                self.names.entry(id).or_insert_with(|| name.to_string());
+                Ok(id)
             }
+        }
+    }
+
+    /// Name of an actor or action id, if it was registered by a loader, the derive or `register_name`.
+    pub fn name(&self, id: u64) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
 
-            if field_angle == Angle::Any {
-                let any = sprite.clone();
-                for s in action.values_mut() {
-                    if s.image.is_none() {
-                        s.image.clone_from(&any.image);
-                    }
-                    if s.layout.is_none() {
-                        s.layout.clone_from(&any.layout);
-                    }
+    /// Refresh entities of an actor after its spritesheets are changed directly on the map.
+    pub fn mark_changed(&mut self, actor: u64) {
+        self.changed.insert(actor);
+    }
+
+    /// Whether the actor or its action, or one of their ancestors, is removed and not added again.
+    pub fn is_removed(&self, actor: u64, action: u64) -> bool {
+        self.lineage(actor)
+            .any(|actor| self.removed.contains(&(actor, None)) || self.removed.contains(&(actor, Some(action))))
+    }
+
+    /// Take the actors changed since last call.
+    pub(crate) fn take_changed(&mut self) -> HashSet<u64> {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Make an actor inherit the actions it does not have from a parent actor.
+    ///
+    /// Variants of an actor then only need the spritesheets which differ. Returns `false`, and keeps the current
+    /// parent, if the parent already inherits from the actor.
+    pub fn set_parent(&mut self, actor: u64, parent: u64) -> bool {
+        if self.lineage(parent).any(|ancestor| ancestor == actor) {
+            return false;
+        }
+        self.parents.insert(actor, parent);
+        self.changed.insert(actor);
+        true
+    }
+
+    /// Stop an actor from inheriting actions of its parent.
+    pub fn remove_parent(&mut self, actor: u64) -> Option<u64> {
+        let parent = self.parents.remove(&actor)?;
+        self.changed.insert(actor);
+        Some(parent)
+    }
+
+    pub fn parent(&self, actor: u64) -> Option<u64> {
+        self.parents.get(&actor).copied()
+    }
+
+    /// Actors which have a parent, with their parent.
+    pub fn parents(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.parents.iter().map(|(&actor, &parent)| (actor, parent))
+    }
+
+    /// The actor followed by its parent, the parent of its parent, and so on.
+    pub fn lineage(&self, actor: u64) -> impl Iterator<Item = u64> + '_ {
+        std::iter::successors(Some(actor), |actor| self.parent(*actor)).take(self.parents.len() + 1)
+    }
+
+    /// Add a level of detail to an actor's action, used from `min_distance` to the camera.
+    ///
+    /// Level 0 is the spritesheets of the action. Each added level, sorted by distance, is used by entities whose
+    /// `View2dActor::lod` is at least its index. Like `add_sprite_sheet`, fields which a level does not set, such as
+    /// the image when only `frames` is reduced, are taken from its `Any` angle, then from the spritesheets of the
+    /// action as they are when the level is added.
+    pub fn add_lod(&mut self, actor: u64, action: u64, min_distance: f32, mut sheets: AngleSpriteSheets) {
+        if let Some(base) = self.get(&actor).and_then(|actions| actions.get(&action)) {
+            let any = sheets.get(&Angle::Any).cloned();
+            for (angle, base_sheet) in base.iter() {
+                let sheet = sheets.entry(*angle).or_default();
+                if let Some(any) = any.as_ref() {
+                    sheet.fill_from(any);
                 }
+                sheet.fill_from(base_sheet);
             }
         }
+
+        let levels = self.lods.entry((actor, action)).or_default();
+        let index = levels.partition_point(|level| level.min_distance <= min_distance);
+        levels.insert(index, LodLevel { min_distance, sheets });
+        self.changed.insert(actor);
+    }
+
+    /// Remove every level of detail of an actor's action.
+    pub fn remove_lods(&mut self, actor: u64, action: u64) -> Vec<LodLevel> {
+        self.changed.insert(actor);
+        self.lods.remove(&(actor, action)).unwrap_or_default()
+    }
+
+    /// Levels of detail of an actor's action, sorted by distance, without the spritesheets of the action itself.
+    pub fn lods(&self, actor: u64, action: u64) -> &[LodLevel] {
+        self.lods.get(&(actor, action)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether any action has levels of detail.
+    pub fn has_lods(&self) -> bool {
+        !self.lods.is_empty()
+    }
+
+    /// Level of detail of an actor's action at a distance to the camera.
+    pub fn lod_level(&self, actor: u64, action: u64, distance: f32) -> usize {
+        self.find_sheets(actor, action)
+            .next()
+            .and_then(|(actor, action, _)| self.lods.get(&(actor, action)))
+            .map_or(0, |levels| {
+                levels.partition_point(|level| level.min_distance <= distance)
+            })
+    }
+
+    /// Find the spritesheet of an actor's action at an angle, and whether it needs to be flipped.
+    ///
+    /// If the actor does not have the action, it is looked up in the parent chain of the actor, then the `"any"`
+    /// action of the chain is used. If none is found, the `"any"` actor is used. If the angle does not exist, the
+    /// opposite angle is flipped. If the opposite is not available either, the `Any` angle is used. If the action
+    /// can't show the angle this way, the lookup goes on with the next one, so a variant which only has some angles
+    /// of an action takes the others from its parent.
+    pub fn get_sprite_sheet(&self, actor: u64, action: u64, angle: Angle) -> Option<(&SpriteSheet, bool)> {
+        self.get_sprite_sheet_lod(actor, action, angle, 0)
+    }
+
+    /// Same as `get_sprite_sheet`, at a level of detail. See `add_lod`.
+    pub fn get_sprite_sheet_lod(
+        &self,
+        actor: u64,
+        action: u64,
+        angle: Angle,
+        lod: usize,
+    ) -> Option<(&SpriteSheet, bool)> {
+        self.find_sheets(actor, action).find_map(|(actor, action, sheets)| {
+            let levels = self
+                .lods
+                .get(&(actor, action))
+                .map_or(&[][..], |levels| &levels[..lod.min(levels.len())]);
+            levels
+                .iter()
+                .rev()
+                .find_map(|level| pick_angle(&level.sheets, angle))
+                .or_else(|| pick_angle(sheets, angle))
+        })
+    }
+
+    /// Actor and action ids where the spritesheets of an actor's action may be found, with the spritesheets, in the
+    /// order they are looked up.
+    fn find_sheets(&self, actor: u64, action: u64) -> impl Iterator<Item = (u64, u64, &AngleSpriteSheets)> {
+        let lineage = [action, ANY_ID]
+            .into_iter()
+            .flat_map(move |action| self.lineage(actor).map(move |actor| (actor, action)));
+        let any = [action, ANY_ID].into_iter().map(|action| (ANY_ID, action));
+        lineage
+            .chain(any)
+            .filter_map(|(actor, action)| Some((actor, action, self.get(&actor)?.get(&action)?)))
     }
 }
 
-/// Convert actor/action to number id using xxh3_64
+/// Spritesheet of an angle, or of the flipped opposite angle, or of the `Any` angle.
+fn pick_angle(sheets: &AngleSpriteSheets, angle: Angle) -> Option<(&SpriteSheet, bool)> {
+    if let Some(sheet) = sheets.get(&angle) {
+        Some((sheet, false))
+    } else if let Some(sheet) = angle.opposite().and_then(|opposite| sheets.get(&opposite)) {
+        Some((sheet, true))
+    } else {
+        sheets.get(&Angle::Any).map(|sheet| (sheet, false))
+    }
+}
+
+/// Warn that spritesheets are registered for the actor `"any"`, which every other actor falls back to.
+pub(crate) fn warn_fallback_actor() {
+    warn!(
+        "Spritesheets are registered for the actor `any`, they are shown for every actor which lacks an action. Rename \
+         the actor if it is not meant as the fallback."
+    );
+}
+
+/// Id of the reserved `"any"` actor and action, whose spritesheets are used when the actor or action has none.
+///
+/// Other names with this id are reported as collisions. Registering spritesheets for the actor `"any"` logs a
+/// warning, as they are shown for every actor which lacks an action.
+pub const ANY_ID: u64 = const_xxh3::xxh3_64(b"any");
+
+/// Convert actor/action to number id using xxh3_64.
+///
+/// The name is not remembered. Use `ActorSpriteSheets::register_name` instead for ids of sprite sheets added from
+/// code, so logs, `View2dActor` and manifests can show the name.
 pub fn get_act_id(act: &str) -> u64 {
     xxh3_64(act.as_bytes())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::URect;
+
+    #[test]
+    fn grid_suffix() {
+        assert_eq!(split_grid_suffix("walk_8x1"), ("walk", Some((8, 1))));
+        assert_eq!(split_grid_suffix("frog-idle-2x3"), ("frog-idle", Some((2, 3))));
+        assert_eq!(split_grid_suffix("walk_left"), ("walk_left", None));
+        assert_eq!(split_grid_suffix("walk_8xa"), ("walk_8xa", None));
+        assert_eq!(split_grid_suffix("8x1"), ("8x1", None));
+    }
+
+    /// Scenes and saves written before `frame`, `lod` and `names` were added still load, with the first frame and
+    /// level. Names are set again by the plugin.
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn view_without_new_fields() {
+        use bevy::reflect::serde::TypedReflectDeserializer;
+        use bevy::reflect::{
+            FromReflect,
+            TypeRegistry,
+        };
+        use serde::de::DeserializeSeed;
+
+        let old =
+            "(angle: Left, action: 1, next_action: [], actor: 2, flipped: false, animation_timer: None, notify: [])";
+        let view: View2dActor = ron::de::from_str(old).unwrap();
+        assert_eq!((view.angle, view.actor, view.frame, view.lod), (Angle::Left, 2, 0, 0));
+
+        let mut registry = TypeRegistry::default();
+        registry.register::<View2dActor>();
+        let registration = registry.get(std::any::TypeId::of::<View2dActor>()).unwrap();
+        let reflected = TypedReflectDeserializer::new(registration, &registry)
+            .deserialize(&mut ron::Deserializer::from_str(old).unwrap())
+            .unwrap();
+        let view = View2dActor::from_reflect(reflected.as_partial_reflect()).unwrap();
+        assert_eq!((view.angle, view.actor, view.frame, view.lod), (Angle::Left, 2, 0, 0));
+    }
+
+    #[test]
+    fn auto_grid_cell_size_keeps_the_cell() {
+        let grid = AutoGrid::CellSize(UVec2::new(16, 16))
+            .to_grid(UVec2::new(50, 34), "walk")
+            .unwrap();
+        assert_eq!(grid.tile_size, UVec2::new(16, 16));
+        assert_eq!((grid.columns, grid.rows), (3, 2));
+    }
+
+    #[test]
+    fn auto_grid_splits_the_image() {
+        let size = UVec2::new(64, 32);
+        let grid = AutoGrid::Cells { columns: 4, rows: 2 }.to_grid(size, "walk").unwrap();
+        assert_eq!((grid.tile_size, grid.columns, grid.rows), (UVec2::new(16, 16), 4, 2));
+
+        let grid = AutoGrid::Frames(4).to_grid(size, "walk").unwrap();
+        assert_eq!((grid.tile_size, grid.columns, grid.rows), (UVec2::new(16, 32), 4, 1));
+
+        let grid = AutoGrid::Frames(4).to_grid(UVec2::new(16, 64), "walk").unwrap();
+        assert_eq!((grid.tile_size, grid.columns, grid.rows), (UVec2::new(16, 16), 1, 4));
+
+        let grid = AutoGrid::FromName.to_grid(size, "walk_2x1").unwrap();
+        assert_eq!((grid.tile_size, grid.columns, grid.rows), (UVec2::new(32, 32), 2, 1));
+    }
+
+    #[test]
+    fn auto_grid_without_cells() {
+        let size = UVec2::new(64, 32);
+        assert_eq!(AutoGrid::CellSize(UVec2::ZERO).to_grid(size, "walk"), None);
+        assert_eq!(AutoGrid::CellSize(UVec2::new(128, 16)).to_grid(size, "walk"), None);
+        assert_eq!(AutoGrid::Frames(0).to_grid(size, "walk"), None);
+        assert_eq!(AutoGrid::FromName.to_grid(size, "walk"), None);
+    }
+
+    #[test]
+    fn grid_from_layout() {
+        let grid = GridLayout {
+            tile_size: UVec2::new(16, 24),
+            columns: 3,
+            rows: 2,
+            padding: Some(UVec2::new(2, 1)),
+            offset: Some(UVec2::new(4, 4)),
+        };
+        assert_eq!(GridLayout::from_layout(&grid.to_layout()), Some(grid));
+
+        let grid = GridLayout {
+            tile_size: UVec2::new(16, 16),
+            columns: 1,
+            rows: 3,
+            ..Default::default()
+        };
+        assert_eq!(GridLayout::from_layout(&grid.to_layout()), Some(grid));
+    }
+
+    #[test]
+    fn grid_from_packed_layout() {
+        let mut layout = TextureAtlasLayout::new_empty(UVec2::new(64, 64));
+        layout.add_texture(URect::new(0, 0, 16, 16));
+        layout.add_texture(URect::new(16, 0, 48, 16));
+        assert_eq!(GridLayout::from_layout(&layout), None);
+    }
+
+    fn sheets_with_lod() -> (ActorSpriteSheets, SpriteSheet) {
+        let mut animation2d = ActorSpriteSheets::default();
+        let base = SpriteSheet {
+            frames: vec![0, 1, 2],
+            ..Default::default()
+        };
+        let far = SpriteSheet {
+            frames: vec![0],
+            ..Default::default()
+        };
+        animation2d.add_sprite_sheet(1, 2, Angle::Front, base.clone());
+        animation2d.add_lod(1, 2, 100.0, AngleSpriteSheets::from(vec![(Angle::Front, far)]));
+        animation2d.set_parent(1, 3);
+        (animation2d, base)
+    }
+
+    #[test]
+    fn remove_actor_drops_lods_and_parent() {
+        let (mut animation2d, base) = sheets_with_lod();
+        assert!(animation2d.remove_actor(1).is_some());
+        assert!(!animation2d.has_lods());
+        assert_eq!(animation2d.parent(1), None);
+        assert!(animation2d.is_removed(1, 2));
+
+        animation2d.add_sprite_sheet(1, 2, Angle::Front, base.clone());
+        let (sheet, _) = animation2d.get_sprite_sheet_lod(1, 2, Angle::Front, 1).unwrap();
+        assert_eq!(sheet.frames, base.frames);
+        assert!(!animation2d.is_removed(1, 2));
+    }
+
+    #[test]
+    fn remove_action_drops_lods() {
+        let (mut animation2d, base) = sheets_with_lod();
+        animation2d.add_sprite_sheet(1, 4, Angle::Front, base.clone());
+        assert!(animation2d.remove_action(1, 2).is_some());
+        assert!(!animation2d.has_lods());
+        assert_eq!(animation2d.parent(1), Some(3));
+
+        animation2d.add_sprite_sheet(1, 2, Angle::Front, base.clone());
+        let (sheet, _) = animation2d.get_sprite_sheet_lod(1, 2, Angle::Front, 1).unwrap();
+        assert_eq!(sheet.frames, base.frames);
+    }
+}
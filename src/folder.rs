@@ -0,0 +1,151 @@
+use crate::component::{
+    ActorSpriteSheets,
+    Angle,
+    AutoGrid,
+    GridLayout,
+    SpriteSheet,
+    split_grid_suffix,
+};
+use bevy::asset::{
+    AssetPath,
+    Assets,
+    Handle,
+    LoadedFolder,
+};
+use bevy::image::Image;
+use bevy::prelude::{
+    Resource,
+    TextureAtlasLayout,
+};
+use std::path::Path;
+
+/// Folders of sprite sheets to register into `ActorSpriteSheets` once loaded.
+///
+/// Images in a folder are organized as `<actor>/<action>/<angle>.<ext>`, such as `frog/idle/left.png`.
+/// The layout of an image is read from a sidecar file next to it, `<angle>.grid.ron` (with feature `serialize`).
+/// A sidecar `any.grid.ron` is used by every angle of the action. An image named with its columns and rows, such as
+/// `left_8x1.png`, is split into that grid. Otherwise, the grid of the folder is used.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::{GridLayout, SpriteSheetFolders};
+///
+/// fn setup(asset_server: Res<AssetServer>, mut folders: ResMut<SpriteSheetFolders>) {
+///     let grid = GridLayout {
+///         tile_size: UVec2::new(16, 16),
+///         columns: 1,
+///         rows: 3,
+///         ..default()
+///     };
+///     folders.add(asset_server.load_folder("actors"), Some(grid));
+/// }
+/// ```
+#[derive(Resource, Default)]
+pub struct SpriteSheetFolders {
+    folders: Vec<SpriteSheetFolder>,
+}
+
+/// Folder of sprite sheets with the grid of images which have no sidecar layout
+pub struct SpriteSheetFolder {
+    pub folder: Handle<LoadedFolder>,
+    pub grid: Option<GridLayout>,
+    pub(crate) registered: bool,
+}
+
+impl SpriteSheetFolders {
+    /// Register images of a folder once it is loaded, using `grid` for images without sidecar layout.
+    pub fn add(&mut self, folder: Handle<LoadedFolder>, grid: Option<GridLayout>) {
+        self.folders.push(SpriteSheetFolder {
+            folder,
+            grid,
+            registered: false,
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpriteSheetFolder> {
+        self.folders.iter()
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut SpriteSheetFolder> {
+        self.folders.iter_mut()
+    }
+}
+
+impl SpriteSheetFolder {
+    /// Whether images of this folder have been stored into `ActorSpriteSheets`.
+    pub fn is_registered(&self) -> bool {
+        self.registered
+    }
+}
+
+impl ActorSpriteSheets {
+    /// Store every image of a loaded folder organized as `<actor>/<action>/<angle>.<ext>`.
+    /// See `SpriteSheetFolders`.
+    pub fn load_folder(
+        &mut self,
+        folder_path: &AssetPath,
+        folder: &LoadedFolder,
+        grid: Option<&GridLayout>,
+        layouts: &mut Assets<TextureAtlasLayout>,
+    ) {
+        let grid_layout = grid.map(|grid| layouts.add(grid.to_layout()));
+
+        for handle in &folder.handles {
+            let Some(path) = handle.path() else {
+                continue;
+            };
+            let Some((actor, action, angle)) = parse_folder_path(folder_path, path) else {
+                continue;
+            };
+
+            let sheet = if let Ok(image) = handle.clone().try_typed::<Image>() {
+                let dir = path.path().parent();
+                let (stem, grid_suffix) = file_stem(path.path()).map(split_grid_suffix).unzip();
+                let has_sidecar = folder.handles.iter().filter_map(|handle| handle.path()).any(|other| {
+                    let other_stem = file_stem(other.path());
+                    other.path().parent() == dir
+                        && other.path().to_string_lossy().contains(".grid.")
+                        && (other_stem == stem || other_stem == Some("any"))
+                });
+                // A file name like `left_8x1.png` declares its own grid
+                let auto_grid = grid_suffix
+                    .flatten()
+                    .map(|_| AutoGrid::FromName)
+                    .filter(|_| !has_sidecar);
+                SpriteSheet {
+                    image: Some(image),
+                    layout: grid_layout.clone().filter(|_| !has_sidecar && auto_grid.is_none()),
+                    auto_grid,
+                    ..Default::default()
+                }
+            } else if let Ok(layout) = handle.clone().try_typed::<TextureAtlasLayout>() {
+                SpriteSheet {
+                    layout: Some(layout),
+                    ..Default::default()
+                }
+            } else {
+                continue;
+            };
+
+            let (actor, action) = (self.register_name(&actor), self.register_name(&action));
+            self.add_sprite_sheet(actor, action, angle, sheet);
+        }
+    }
+}
+
+/// Split `<folder>/<actor>/<action>/<angle>[_<columns>x<rows>].<ext>` into actor, action and angle.
+fn parse_folder_path(folder: &AssetPath, path: &AssetPath) -> Option<(String, String, Angle)> {
+    let relative = path.path().strip_prefix(folder.path()).ok()?;
+    let parts: Vec<&str> = relative.iter().map(|part| part.to_str()).collect::<Option<_>>()?;
+    let [actor, action, file] = parts.as_slice() else {
+        return None;
+    };
+    let angle = split_grid_suffix(file.split('.').next()?).0.parse().ok()?;
+    Some((actor.to_string(), action.to_string(), angle))
+}
+
+/// File name before the first `.`, so the stem of `left.grid.ron` is `left`.
+fn file_stem(path: &Path) -> Option<&str> {
+    path.file_name()?.to_str()?.split('.').next()
+}
@@ -1,27 +1,80 @@
 #![doc=include_str!("../README.md")]
 
+#[cfg(feature = "animated")]
+pub mod animated;
+#[cfg(feature = "aseprite")]
+pub mod aseprite;
+pub mod atlas;
 pub mod component;
+pub mod folder;
+pub mod loader;
+#[cfg(feature = "serialize")]
+pub mod manifest;
+pub mod names;
+pub mod palette;
+pub mod readiness;
 pub mod system;
+#[cfg(feature = "texture_packer")]
+pub mod texture_packer;
+pub mod validate;
 
+pub use crate::atlas::*;
 pub use crate::component::*;
+pub use crate::folder::*;
+pub use crate::loader::*;
+pub use crate::names::*;
+pub use crate::palette::*;
+pub use crate::readiness::*;
 use crate::system::{
     animated_timer,
     animating,
+    build_auto_grids,
+    build_palette_variants,
+    evict_palette_variants,
+    notify_loaded_actors,
+    pack_actor_atlases,
+    refresh_changed_sheets,
+    register_loaded_sheets,
+    register_sheet_folders,
+    select_lod,
+    update_view_names,
     view_changed_event,
 };
+pub use crate::validate::*;
 use bevy::prelude::{
     App,
+    AssetApp,
+    AssetEvent,
+    Image,
     IntoScheduleConfigs,
     Plugin,
+    Res,
     States,
+    SystemCondition,
     Update,
+    any_with_component,
     in_state,
     on_message,
+    resource_changed,
 };
+// Used by the derive macro
+#[doc(hidden)]
+pub use bevy;
+#[cfg(feature = "serialize")]
+#[doc(hidden)]
+pub use serde;
 
 macro_rules! plugin_systems {
     () => {
-        (view_changed_event.run_if(on_message::<ViewChanged>), animated_timer)
+        (
+            (
+                select_lod.run_if(any_with_component::<LodCamera>),
+                refresh_changed_sheets,
+                view_changed_event.run_if(on_message::<ViewChanged>),
+            )
+                .chain(),
+            animated_timer,
+        )
     };
 }
 
@@ -43,8 +96,41 @@ where
     fn build(&self, app: &mut App) {
         app.register_type::<View2dActor>()
             .add_message::<ViewChanged>()
+            .add_message::<ActorSheetsLoaded>()
             .insert_resource(ActorSpriteSheets::default())
+            .init_resource::<SpriteSheetFolders>()
+            .init_resource::<SpriteSheetFiles>()
+            .init_resource::<AtlasPacking>()
+            .init_resource::<RemovedSheetPolicy>()
+            .init_resource::<PaletteVariants>()
+            .init_asset::<LoadedSpriteSheets>()
+            .add_systems(
+                Update,
+                (
+                    register_loaded_sheets,
+                    register_sheet_folders,
+                    build_auto_grids.run_if(resource_changed::<ActorSpriteSheets>.or(on_message::<AssetEvent<Image>>)),
+                    notify_loaded_actors,
+                    pack_actor_atlases
+                        .run_if(|packing: Res<AtlasPacking>| packing.pending().next().is_some())
+                        .after(build_auto_grids),
+                    build_palette_variants.run_if(|palettes: Res<PaletteVariants>| palettes.is_pending()),
+                    evict_palette_variants.run_if(resource_changed::<ActorSpriteSheets>),
+                    update_view_names,
+                ),
+            )
             .add_observer(animating);
+
+        #[cfg(feature = "serialize")]
+        app.register_asset_loader(manifest::SpriteSheetManifestLoader)
+            .register_asset_loader(manifest::GridLayoutLoader);
+        #[cfg(feature = "animated")]
+        app.register_asset_loader(animated::AnimatedImageLoader);
+        #[cfg(feature = "aseprite")]
+        app.register_asset_loader(aseprite::AsepriteLoader);
+        #[cfg(feature = "texture_packer")]
+        app.register_asset_loader(texture_packer::TexturePackerLoader);
+
         if self.states.is_empty() {
             app.add_systems(Update, plugin_systems!());
         } else {
@@ -0,0 +1,201 @@
+use crate::component::{
+    ANY_ID,
+    ActorSpriteSheets,
+    Angle,
+    AngleSpriteSheets,
+    SpriteSheet,
+    get_act_id,
+    warn_fallback_actor,
+};
+use bevy::asset::{
+    Asset,
+    AssetId,
+    Handle,
+    UntypedAssetId,
+    VisitAssetDependencies,
+};
+use bevy::log::warn;
+use bevy::math::{
+    URect,
+    UVec2,
+};
+use bevy::platform::collections::HashSet;
+use bevy::prelude::Resource;
+use bevy::reflect::TypePath;
+
+/// Sprite sheets read from a file by one of the asset loaders of this plugin.
+///
+/// Once loaded with all of its images, the sprite sheets are registered into `ActorSpriteSheets` automatically.
+/// Keep the handle alive until then, or the loading may be cancelled.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::LoadedSpriteSheets;
+///
+/// #[derive(Resource)]
+/// struct FrogSheets(Handle<LoadedSpriteSheets>);
+///
+/// fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+///     commands.insert_resource(FrogSheets(asset_server.load("frog_left.aseprite.json")));
+/// }
+/// ```
+#[derive(TypePath, Default, Clone)]
+pub struct LoadedSpriteSheets {
+    pub sheets: Vec<NamedSpriteSheet>,
+    /// Named regions of the frames, such as hitboxes or pivots
+    pub slices: Vec<SpriteSlice>,
+    /// Names of actors and their parent actor, see `ActorSpriteSheets::set_parent`
+    pub parents: Vec<(String, String)>,
+    /// Levels of detail of actions, see `ActorSpriteSheets::add_lod`
+    pub lods: Vec<NamedLod>,
+}
+
+/// Sprite sheet of an actor's action at one angle, identified by names
+#[derive(Default, Clone)]
+pub struct NamedSpriteSheet {
+    pub actor: String,
+    pub action: String,
+    pub angle: Angle,
+    pub sheet: SpriteSheet,
+}
+
+/// Level of detail of an actor's action, identified by names
+#[derive(Default, Clone)]
+pub struct NamedLod {
+    pub actor: String,
+    pub action: String,
+    pub min_distance: f32,
+    pub sheets: Vec<(Angle, SpriteSheet)>,
+}
+
+/// Named region which changes over frames
+#[derive(Default, Clone)]
+pub struct SpriteSlice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+/// Region of a slice, starting from an atlas index until the next key
+#[derive(Default, Clone)]
+pub struct SliceKey {
+    pub frame: usize,
+    pub rect: URect,
+    pub pivot: Option<UVec2>,
+}
+
+/// Sprite sheet files which loading screens wait on, see `SpriteSheetsReadiness`.
+///
+/// Files which are not added here are only seen by `SpriteSheetsReadiness` once they are registered.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::SpriteSheetFiles;
+///
+/// fn setup(asset_server: Res<AssetServer>, mut files: ResMut<SpriteSheetFiles>) {
+///     files.add(asset_server.load("frog_left.aseprite.json"));
+/// }
+/// ```
+#[derive(Resource, Default)]
+pub struct SpriteSheetFiles {
+    files: Vec<SpriteSheetFile>,
+}
+
+/// Sprite sheet file which is kept alive until it is registered
+pub struct SpriteSheetFile {
+    pub handle: Handle<LoadedSpriteSheets>,
+    pub(crate) registered: bool,
+}
+
+impl SpriteSheetFiles {
+    /// Wait on a file until it is loaded with its images and registered into `ActorSpriteSheets`.
+    pub fn add(&mut self, handle: Handle<LoadedSpriteSheets>) {
+        self.files.push(SpriteSheetFile {
+            handle,
+            registered: false,
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpriteSheetFile> {
+        self.files.iter()
+    }
+
+    pub(crate) fn registered(&mut self, id: AssetId<LoadedSpriteSheets>) {
+        for file in self.files.iter_mut().filter(|file| file.handle.id() == id) {
+            file.registered = true;
+        }
+    }
+}
+
+impl SpriteSheetFile {
+    /// Whether the sprite sheets of this file have been stored into `ActorSpriteSheets`.
+    pub fn is_registered(&self) -> bool {
+        self.registered
+    }
+}
+
+impl Asset for LoadedSpriteSheets {}
+
+impl VisitAssetDependencies for LoadedSpriteSheets {
+    fn visit_dependencies(&self, visit: &mut impl FnMut(UntypedAssetId)) {
+        for named in &self.sheets {
+            named.sheet.visit_dependencies(visit);
+        }
+        for (_, sheet) in self.lods.iter().flat_map(|lod| &lod.sheets) {
+            sheet.visit_dependencies(visit);
+        }
+    }
+}
+
+impl SpriteSlice {
+    /// Region of this slice at an atlas index.
+    pub fn get(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().rev().find(|key| key.frame <= frame)
+    }
+}
+
+impl ActorSpriteSheets {
+    /// Store spritesheets read from a file. Actor and action names are converted with `get_act_id`.
+    pub fn load_sprite_sheets(&mut self, loaded: &LoadedSpriteSheets) {
+        if loaded.sheets.iter().any(|named| get_act_id(&named.actor) == ANY_ID) {
+            warn_fallback_actor();
+        }
+        for named in &loaded.sheets {
+            let actor = self.register_name(&named.actor);
+            let action = self.register_name(&named.action);
+            self.add_sprite_sheet(actor, action, named.angle, named.sheet.clone());
+        }
+        // Levels of the file replace the ones of a previous load instead of adding to them
+        let mut replaced = HashSet::new();
+        for lod in &loaded.lods {
+            let actor = self.register_name(&lod.actor);
+            let action = self.register_name(&lod.action);
+            if replaced.insert((actor, action)) {
+                self.remove_lods(actor, action);
+            }
+            self.add_lod(
+                actor,
+                action,
+                lod.min_distance,
+                AngleSpriteSheets::from(lod.sheets.clone()),
+            );
+        }
+        for (actor, parent) in &loaded.parents {
+            let (actor_id, parent_id) = (self.register_name(actor), self.register_name(parent));
+            if !self.set_parent(actor_id, parent_id) {
+                warn!("Actor {actor} is not given the parent {parent}, which already inherits from it");
+            }
+        }
+    }
+}
+
+/// Split a name like `frog_front_left` into the name and the angle at its end: `("frog", Some(Angle::FrontLeft))`.
+pub fn split_angle_suffix(name: &str) -> (&str, Option<Angle>) {
+    for (i, _) in name.match_indices('_') {
+        if let Ok(angle) = name[i + 1..].parse() {
+            return (&name[..i], Some(angle));
+        }
+    }
+    (name, None)
+}
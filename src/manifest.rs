@@ -0,0 +1,620 @@
+use crate::component::{
+    ActorSpriteSheets,
+    Angle,
+    AngleSpriteSheets,
+    AutoGrid,
+    GridLayout,
+    SpriteSheet,
+};
+use crate::loader::{
+    LoadedSpriteSheets,
+    NamedLod,
+    NamedSpriteSheet,
+};
+use bevy::asset::io::{
+    AssetSourceId,
+    Reader,
+};
+use bevy::asset::{
+    AssetLoader,
+    Assets,
+    Handle,
+    LoadContext,
+};
+use bevy::image::Image;
+use bevy::math::{
+    URect,
+    UVec2,
+};
+use bevy::prelude::TextureAtlasLayout;
+use bevy::reflect::TypePath;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::time::Duration;
+
+/// Declaration of actors and their sprite sheets, so they can be added without recompiling.
+///
+/// Angles are stored in order, with the same semantics as `ActorSpriteSheets::load_asset_loader`: fields which are
+/// not set are taken from the `Any` angle of the same action. Image paths are relative to the manifest file, or to the
+/// asset folder if they start with `/`.
+///
+/// Example of `frog.sheets.ron`:
+/// ```ron
+/// (
+///     actors: [
+///         (
+///             name: "frog",
+///             actions: [
+///                 (
+///                     name: "idle",
+///                     angles: [
+///                         (angle: Any, layout: Some((tile_size: (16, 16), columns: 1, rows: 3))),
+///                         (angle: Front, image: Some("frog_idle_front.png")),
+///                         (angle: Back, image: Some("frog_idle_back.png")),
+///                         (angle: Left, image: Some("frog_idle_left.png")),
+///                     ],
+///                 ),
+///             ],
+///         ),
+///     ],
+/// )
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct SpriteSheetManifest {
+    pub actors: Vec<ActorManifest>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct ActorManifest {
+    pub name: String,
+    /// Actor to take the actions this actor does not have from
+    #[serde(default)]
+    pub parent: Option<String>,
+    pub actions: Vec<ActionManifest>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct ActionManifest {
+    pub name: String,
+    pub angles: Vec<AngleManifest>,
+    /// Levels of detail, see `ActorSpriteSheets::add_lod`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lods: Vec<LodManifest>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct LodManifest {
+    pub min_distance: f32,
+    /// Angles of the level, with fields which are not set taken from its `Any` angle, then from the action
+    pub angles: Vec<AngleManifest>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct AngleManifest {
+    pub angle: Angle,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub layout: Option<GridLayout>,
+    /// Cells of a layout which is not a grid
+    #[serde(default)]
+    pub rects: Option<RectsLayout>,
+    /// Grid built from the image size once it is loaded, if `layout` is not set
+    #[serde(default)]
+    pub auto_grid: Option<AutoGrid>,
+    /// One image per frame, shown instead of `image` and `layout`
+    #[serde(default)]
+    pub images: Vec<String>,
+    /// Atlas indices of the animation frames, in play order
+    #[serde(default)]
+    pub frames: Vec<usize>,
+    /// Duration of each animation frame, in milliseconds
+    #[serde(default)]
+    pub durations: Vec<u64>,
+}
+
+/// Layout of cells which don't form a grid, in the order of their atlas indices
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RectsLayout {
+    pub size: UVec2,
+    pub rects: Vec<URect>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SpriteSheetManifestExportError {
+    #[error("no name is registered for id {0}")]
+    UnnamedId(u64),
+    #[error("image of {actor}/{action} at angle {angle:?} has no asset path, such as a packed or recolored image")]
+    UnsavedImage {
+        actor: String,
+        action: String,
+        angle: Angle,
+    },
+    #[error("could not write manifest ron: {0}")]
+    Ron(#[from] ron::Error),
+    #[error("could not write manifest json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl SpriteSheetManifest {
+    /// Write as pretty RON, the format of `*.sheets.ron`.
+    pub fn to_ron(&self) -> Result<String, SpriteSheetManifestExportError> {
+        Ok(ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?)
+    }
+
+    /// Write as pretty JSON, the format of `*.sheets.json`.
+    pub fn to_json(&self) -> Result<String, SpriteSheetManifestExportError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl ActorSpriteSheets {
+    /// Describe every spritesheet as a manifest which `SpriteSheetManifestLoader` can load back.
+    ///
+    /// Actors and actions are sorted by name so the output is stable for diffing. Fields which an angle takes from
+    /// the `Any` angle are left out. Image paths are written relative to the asset folder. Layouts are written as a
+    /// grid if they are one, otherwise as rects. Layouts which are not loaded are left out. Actors which only have a
+    /// parent are written too, and levels of detail are written with every field of their angles.
+    ///
+    /// Every actor and action id needs a name, see `ActorSpriteSheets::register_name`. Images which were not loaded
+    /// from a file, such as packed atlases and palette variants, can't be written and return
+    /// `SpriteSheetManifestExportError::UnsavedImage`.
+    pub fn to_manifest(
+        &self,
+        layouts: &Assets<TextureAtlasLayout>,
+    ) -> Result<SpriteSheetManifest, SpriteSheetManifestExportError> {
+        let name = |id: u64| self.name(id).ok_or(SpriteSheetManifestExportError::UnnamedId(id));
+
+        let mut ids: Vec<u64> = self
+            .keys()
+            .copied()
+            .chain(self.parents().map(|(actor, _)| actor))
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let mut actors = Vec::new();
+        for actor in ids {
+            let mut action_manifests = Vec::new();
+            for (&action, sheets) in self.get(&actor).into_iter().flatten() {
+                let (actor_name, action_name) = (name(actor)?, name(action)?);
+                let angles = |sheets: &AngleSpriteSheets, skip_any: bool| {
+                    let any = sheets.get(&Angle::Any).filter(|_| skip_any);
+                    Angle::ALL
+                        .into_iter()
+                        .filter_map(|angle| Some((angle, sheets.get(&angle)?)))
+                        .map(|(angle, sheet)| {
+                            angle_manifest(angle, sheet, any.filter(|_| angle != Angle::Any), layouts).ok_or_else(
+                                || SpriteSheetManifestExportError::UnsavedImage {
+                                    actor: actor_name.to_string(),
+                                    action: action_name.to_string(),
+                                    angle,
+                                },
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                };
+                // Levels are filled from the action when they are added, so only writing every field keeps them
+                let lods = self
+                    .lods(actor, action)
+                    .iter()
+                    .map(|level| {
+                        Ok(LodManifest {
+                            min_distance: level.min_distance,
+                            angles: angles(&level.sheets, false)?,
+                        })
+                    })
+                    .collect::<Result<_, SpriteSheetManifestExportError>>()?;
+                action_manifests.push(ActionManifest {
+                    name: action_name.to_string(),
+                    angles: angles(sheets, true)?,
+                    lods,
+                });
+            }
+            action_manifests.sort_by(|a, b| a.name.cmp(&b.name));
+            actors.push(ActorManifest {
+                name: name(actor)?.to_string(),
+                parent: self.parent(actor).map(name).transpose()?.map(str::to_string),
+                actions: action_manifests,
+            });
+        }
+        actors.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(SpriteSheetManifest { actors })
+    }
+}
+
+fn angle_manifest(
+    angle: Angle,
+    sheet: &SpriteSheet,
+    any: Option<&SpriteSheet>,
+    layouts: &Assets<TextureAtlasLayout>,
+) -> Option<AngleManifest> {
+    let mut manifest = AngleManifest {
+        angle,
+        ..Default::default()
+    };
+
+    if any.is_none_or(|any| any.image != sheet.image) {
+        manifest.image = match &sheet.image {
+            Some(image) => Some(image_path(image)?),
+            None => None,
+        };
+    }
+    if any.is_none_or(|any| any.images != sheet.images) {
+        manifest.images = sheet.images.iter().map(image_path).collect::<Option<_>>()?;
+    }
+    if any.is_none_or(|any| any.layout != sheet.layout)
+        && let Some(layout) = sheet.layout.as_ref().and_then(|layout| layouts.get(layout))
+    {
+        match GridLayout::from_layout(layout) {
+            Some(grid) => manifest.layout = Some(grid),
+            None => {
+                manifest.rects = Some(RectsLayout {
+                    size: layout.size,
+                    rects: layout.textures.clone(),
+                })
+            }
+        }
+    }
+    if any.is_none_or(|any| any.frames != sheet.frames) {
+        manifest.frames.clone_from(&sheet.frames);
+    }
+    if any.is_none_or(|any| any.durations != sheet.durations) {
+        manifest.durations = sheet
+            .durations
+            .iter()
+            .map(|duration| duration.as_millis() as u64)
+            .collect();
+    }
+    if any.is_none_or(|any| any.auto_grid != sheet.auto_grid) && sheet.layout.is_none() {
+        manifest.auto_grid = sheet.auto_grid;
+    }
+    Some(manifest)
+}
+
+fn image_path(image: &Handle<Image>) -> Option<String> {
+    let path = image.path()?;
+    // Resolved from the asset folder, wherever the manifest is saved
+    Some(if *path.source() == AssetSourceId::Default { format!("/{path}") } else { path.to_string() })
+}
+
+/// Load a `SpriteSheetManifest` from `*.sheets.ron` or `*.sheets.json`.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::LoadedSpriteSheets;
+///
+/// fn setup(asset_server: Res<AssetServer>) {
+///     let sheets: Handle<LoadedSpriteSheets> = asset_server.load("frog.sheets.ron");
+/// }
+/// ```
+#[derive(Default, TypePath)]
+pub struct SpriteSheetManifestLoader;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SpriteSheetManifestLoaderError {
+    #[error("could not read manifest file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse manifest ron: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("could not parse manifest json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid image path `{0}`")]
+    ImagePath(String),
+}
+
+impl AssetLoader for SpriteSheetManifestLoader {
+    type Asset = LoadedSpriteSheets;
+    type Settings = ();
+    type Error = SpriteSheetManifestLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let is_json = load_context.path().path().extension().is_some_and(|ext| ext == "json");
+        let manifest: SpriteSheetManifest =
+            if is_json { serde_json::from_slice(&bytes)? } else { ron::de::from_bytes(&bytes)? };
+
+        let mut sheets = Vec::new();
+        let mut parents = Vec::new();
+        let mut lods = Vec::new();
+        for actor in manifest.actors {
+            if let Some(parent) = &actor.parent {
+                parents.push((actor.name.clone(), parent.clone()));
+            }
+            for action in actor.actions {
+                for angle in action.angles {
+                    let label = format!("{}/{}/{:?}", actor.name, action.name, angle.angle);
+                    sheets.push(NamedSpriteSheet {
+                        actor: actor.name.clone(),
+                        action: action.name.clone(),
+                        angle: angle.angle,
+                        sheet: load_angle(load_context, &label, angle)?,
+                    });
+                }
+                for (i, lod) in action.lods.into_iter().enumerate() {
+                    let mut angles = Vec::new();
+                    for angle in lod.angles {
+                        let label = format!("{}/{}/lod{}/{:?}", actor.name, action.name, i + 1, angle.angle);
+                        angles.push((angle.angle, load_angle(load_context, &label, angle)?));
+                    }
+                    lods.push(NamedLod {
+                        actor: actor.name.clone(),
+                        action: action.name.clone(),
+                        min_distance: lod.min_distance,
+                        sheets: angles,
+                    });
+                }
+            }
+        }
+
+        Ok(LoadedSpriteSheets {
+            sheets,
+            parents,
+            lods,
+            ..Default::default()
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sheets.ron", "sheets.json"]
+    }
+}
+
+/// Spritesheet of an angle, with its layout added as `{label}/layout`.
+fn load_angle(
+    load_context: &mut LoadContext<'_>,
+    label: &str,
+    angle: AngleManifest,
+) -> Result<SpriteSheet, SpriteSheetManifestLoaderError> {
+    let mut load_image = |path: String| {
+        let path = load_context
+            .path()
+            .resolve_embed(&path)
+            .map_err(|_| SpriteSheetManifestLoaderError::ImagePath(path.clone()))?;
+        Ok::<Handle<Image>, SpriteSheetManifestLoaderError>(load_context.load(path))
+    };
+    let image = angle.image.map(&mut load_image).transpose()?;
+    let images = angle.images.into_iter().map(load_image).collect::<Result<_, _>>()?;
+    let layout = angle.layout.map(|grid| grid.to_layout()).or_else(|| {
+        let RectsLayout { size, rects } = angle.rects?;
+        let mut layout = TextureAtlasLayout::new_empty(size);
+        for rect in rects {
+            layout.add_texture(rect);
+        }
+        Some(layout)
+    });
+    let layout = layout.map(|layout| load_context.add_labeled_asset(format!("{label}/layout"), layout));
+
+    Ok(SpriteSheet {
+        image,
+        layout,
+        frames: angle.frames,
+        durations: angle.durations.into_iter().map(Duration::from_millis).collect(),
+        auto_grid: angle.auto_grid,
+        images,
+    })
+}
+
+/// Load a `GridLayout` from `*.grid.ron` or `*.grid.json` as a `TextureAtlasLayout`.
+///
+/// Example of `left.grid.ron`:
+/// ```ron
+/// (tile_size: (16, 16), columns: 1, rows: 3)
+/// ```
+#[derive(Default, TypePath)]
+pub struct GridLayoutLoader;
+
+impl AssetLoader for GridLayoutLoader {
+    type Asset = TextureAtlasLayout;
+    type Settings = ();
+    type Error = SpriteSheetManifestLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let is_json = load_context.path().path().extension().is_some_and(|ext| ext == "json");
+        let grid: GridLayout = if is_json { serde_json::from_slice(&bytes)? } else { ron::de::from_bytes(&bytes)? };
+        Ok(grid.to_layout())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["grid.ron", "grid.json"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sheets(layouts: &mut Assets<TextureAtlasLayout>) -> ActorSpriteSheets {
+        let grid = GridLayout {
+            tile_size: UVec2::new(16, 16),
+            columns: 1,
+            rows: 3,
+            ..Default::default()
+        };
+        let mut rects = TextureAtlasLayout::new_empty(UVec2::new(64, 16));
+        rects.add_texture(URect::new(0, 0, 16, 16));
+        rects.add_texture(URect::new(16, 0, 48, 16));
+
+        let mut animation2d = ActorSpriteSheets::default();
+        let (frog, idle, tadpole) = (
+            animation2d.register_name("frog"),
+            animation2d.register_name("idle"),
+            animation2d.register_name("tadpole"),
+        );
+        let any = SpriteSheet {
+            layout: Some(layouts.add(grid.to_layout())),
+            ..Default::default()
+        };
+        animation2d.add_sprite_sheet(frog, idle, Angle::Any, any);
+        let front = SpriteSheet {
+            frames: vec![0, 2, 1],
+            durations: vec![Duration::from_millis(100); 3],
+            ..Default::default()
+        };
+        animation2d.add_sprite_sheet(frog, idle, Angle::Front, front);
+        let left = SpriteSheet {
+            layout: Some(layouts.add(rects)),
+            ..Default::default()
+        };
+        animation2d.add_sprite_sheet(frog, idle, Angle::Left, left);
+        animation2d.add_sprite_sheet(tadpole, idle, Angle::Back, SpriteSheet::default());
+        animation2d.set_parent(tadpole, frog);
+        let far = SpriteSheet {
+            frames: vec![0],
+            ..Default::default()
+        };
+        animation2d.add_lod(frog, idle, 50., AngleSpriteSheets::from(vec![(Angle::Any, far)]));
+        // Only inherits its actions
+        let froglet = animation2d.register_name("froglet");
+        animation2d.set_parent(froglet, frog);
+        animation2d
+    }
+
+    #[test]
+    fn export_manifest() {
+        let mut layouts = Assets::default();
+        let manifest = sheets(&mut layouts).to_manifest(&layouts).unwrap();
+
+        let names: Vec<_> = manifest.actors.iter().map(|actor| actor.name.as_str()).collect();
+        assert_eq!(names, ["frog", "froglet", "tadpole"]);
+        assert_eq!(manifest.actors[1].parent.as_deref(), Some("frog"));
+        assert!(manifest.actors[1].actions.is_empty());
+        assert_eq!(manifest.actors[2].parent.as_deref(), Some("frog"));
+
+        let lods = &manifest.actors[0].actions[0].lods;
+        assert_eq!(lods.len(), 1);
+        assert_eq!(lods[0].min_distance, 50.);
+        // Every field is written, including the ones filled from the action
+        let lod_angles: Vec<_> = lods[0]
+            .angles
+            .iter()
+            .map(|angle| (angle.angle, angle.frames.clone()))
+            .collect();
+        assert_eq!(
+            lod_angles,
+            [(Angle::Any, vec![0]), (Angle::Front, vec![0]), (Angle::Left, vec![0])]
+        );
+        assert_eq!(lods[0].angles[1].layout.as_ref().map(|grid| grid.rows), Some(3));
+
+        let angles = &manifest.actors[0].actions[0].angles;
+        let [any, front, left] = &angles[..] else {
+            panic!("expected 3 angles, got {angles:?}");
+        };
+        assert_eq!(
+            (any.angle, front.angle, left.angle),
+            (Angle::Any, Angle::Front, Angle::Left)
+        );
+        assert_eq!(any.layout.as_ref().map(|grid| grid.rows), Some(3));
+        // Taken from `Any`, so not repeated
+        assert!(front.layout.is_none() && front.rects.is_none());
+        assert_eq!(front.frames, [0, 2, 1]);
+        assert_eq!(front.durations, [100; 3]);
+        assert_eq!(left.rects.as_ref().map(|rects| rects.rects.len()), Some(2));
+    }
+
+    #[test]
+    fn manifest_round_trip() {
+        use bevy::asset::{
+            AssetApp,
+            AssetPlugin,
+            AssetServer,
+        };
+        use bevy::prelude::{
+            App,
+            MinimalPlugins,
+        };
+
+        let mut layouts = Assets::default();
+        let mut manifest = sheets(&mut layouts).to_manifest(&layouts).unwrap();
+        // As if loaded from a file, at the action and at the level filled from it
+        let image = Some("/frog_idle_front.png".to_string());
+        manifest.actors[0].actions[0].angles[1].image.clone_from(&image);
+        manifest.actors[0].actions[0].lods[0].angles[1].image = image;
+        let ron = manifest.to_ron().unwrap();
+
+        let dir = std::env::temp_dir().join(format!("bevy_2dviewangle_manifest_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("frog.sheets.ron"), &ron).unwrap();
+        std::fs::write(dir.join("frog.sheets.json"), manifest.to_json().unwrap()).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: dir.to_string_lossy().into_owned(),
+                ..Default::default()
+            },
+        ))
+        .init_asset::<Image>()
+        .init_asset::<TextureAtlasLayout>()
+        .init_asset::<LoadedSpriteSheets>()
+        .register_asset_loader(SpriteSheetManifestLoader);
+
+        for file in ["frog.sheets.ron", "frog.sheets.json"] {
+            let handle: Handle<LoadedSpriteSheets> = app.world().resource::<AssetServer>().load(file);
+            let mut tries = 0;
+            while !app.world().resource::<Assets<LoadedSpriteSheets>>().contains(&handle) {
+                assert!(tries < 500, "{file} is not loaded");
+                tries += 1;
+                app.update();
+                std::thread::sleep(Duration::from_millis(10));
+            }
+
+            let mut animation2d = ActorSpriteSheets::default();
+            animation2d.load_sprite_sheets(
+                app.world()
+                    .resource::<Assets<LoadedSpriteSheets>>()
+                    .get(&handle)
+                    .unwrap(),
+            );
+            let exported = animation2d
+                .to_manifest(app.world().resource::<Assets<TextureAtlasLayout>>())
+                .unwrap();
+            assert_eq!(exported.to_ron().unwrap(), ron, "{file}");
+        }
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn export_needs_saved_images() {
+        let mut animation2d = ActorSpriteSheets::default();
+        let (frog, idle) = (animation2d.register_name("frog"), animation2d.register_name("idle"));
+        let packed = SpriteSheet {
+            image: Some(Assets::<Image>::default().add(Image::default())),
+            ..Default::default()
+        };
+        animation2d.add_sprite_sheet(frog, idle, Angle::Left, packed);
+        assert!(matches!(
+            animation2d.to_manifest(&Assets::default()),
+            Err(SpriteSheetManifestExportError::UnsavedImage { angle: Angle::Left, .. })
+        ));
+    }
+
+    #[test]
+    fn export_needs_names() {
+        let mut animation2d = ActorSpriteSheets::default();
+        animation2d.add_sprite_sheet(1, 2, Angle::Front, SpriteSheet::default());
+        assert!(matches!(
+            animation2d.to_manifest(&Assets::default()),
+            Err(SpriteSheetManifestExportError::UnnamedId(_))
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+use crate::component::ActorSpriteSheets;
+use bevy::reflect::Reflect;
+use std::fmt;
+
+/// Actor or action id, shown with its registered name in `Display` and `Debug`.
+///
+/// Made by `ActorSpriteSheets::act_name`. Names are registered by the loaders, manifests, sprite sheet folders, the
+/// derive once loaded with `load_asset_loader`, and `ActorSpriteSheets::register_name`.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::{ActorSpriteSheets, View2dActor};
+///
+/// fn print_actions(animation2d: Res<ActorSpriteSheets>, actors: Query<&View2dActor>) {
+///     for actor in &actors {
+///         info!("{} is playing {}", animation2d.act_name(actor.actor), animation2d.act_name(actor.action));
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ActName<'a> {
+    pub id: u64,
+    pub name: Option<&'a str>,
+}
+
+impl fmt::Display for ActName<'_> {
+    /// The name, or `#id` if it is unknown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => f.write_str(name),
+            None => write!(f, "#{}", self.id),
+        }
+    }
+}
+
+impl fmt::Debug for ActName<'_> {
+    /// The name and the id, or only the id if the name is unknown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{:?} ({})", name, self.id),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+/// Names of the actor and action of a `View2dActor`, kept up to date from `ActorSpriteSheets` by the plugin, so
+/// `Debug` and inspectors show them.
+#[derive(Reflect, Default, Clone, Debug, PartialEq)]
+pub struct ViewNames {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+}
+
+impl ActorSpriteSheets {
+    /// Id with its registered name, to show in logs.
+    pub fn act_name(&self, id: u64) -> ActName<'_> {
+        ActName {
+            id,
+            name: self.name(id),
+        }
+    }
+}
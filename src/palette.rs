@@ -0,0 +1,285 @@
+use crate::component::{
+    ActorSpriteSheets,
+    AngleSpriteSheets,
+    SpriteSheet,
+};
+use bevy::asset::{
+    AssetId,
+    Assets,
+    Handle,
+};
+use bevy::image::Image;
+use bevy::platform::collections::{
+    HashMap,
+    HashSet,
+};
+use bevy::prelude::Resource;
+
+/// Color lookup table of a palette swap. Colors are RGBA bytes as stored in the image, usually sRGB.
+#[derive(Default, Clone, Debug)]
+pub struct Palette {
+    /// Key of the recolored images in the cache, so spritesheets sharing an image and a palette share the result
+    pub key: String,
+    pub colors: HashMap<[u8; 4], [u8; 4]>,
+}
+
+/// Palette-swapped variants of actors, built on CPU once the images of the base actor are loaded.
+///
+/// A variant is registered as a new actor in `ActorSpriteSheets`, with every action of the base actor, including the
+/// ones it inherits from its parents. Recolored images are cached by image and palette key, until no variant which
+/// uses them is left in `ActorSpriteSheets`.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::{Palette, PaletteVariants, get_act_id};
+///
+/// fn setup(mut palettes: ResMut<PaletteVariants>) {
+///     let red = Palette {
+///         key: "red".to_string(),
+///         colors: [([48, 96, 48, 255], [160, 40, 40, 255])].into_iter().collect(),
+///     };
+///     palettes.add(get_act_id("goblin"), "goblin_red", red);
+/// }
+/// ```
+#[derive(Resource, Default)]
+pub struct PaletteVariants {
+    pending: Vec<PaletteVariant>,
+    cache: HashMap<(AssetId<Image>, String), Handle<Image>>,
+    /// Cache keys of the images of each built variant
+    built: HashMap<u64, Vec<(AssetId<Image>, String)>>,
+}
+
+struct PaletteVariant {
+    base: u64,
+    name: String,
+    palette: Palette,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PaletteError {
+    #[error("image data of the actor is not available on CPU")]
+    MissingImageData,
+    #[error("only images with 4 bytes per pixel can be recolored")]
+    UnsupportedFormat,
+}
+
+impl Palette {
+    /// Palette from pairs of source and target colors.
+    pub fn new(key: &str, colors: impl IntoIterator<Item = ([u8; 4], [u8; 4])>) -> Self {
+        Self {
+            key: key.to_string(),
+            colors: colors.into_iter().collect(),
+        }
+    }
+
+    /// Copy of an image with the colors of this palette replaced.
+    pub fn recolor(&self, image: &Image) -> Result<Image, PaletteError> {
+        let mut recolored = image.clone();
+        let data = recolored.data.as_mut().ok_or(PaletteError::MissingImageData)?;
+        let pixel_size = image.pixel_bytes(Default::default()).map_or(0, <[u8]>::len);
+        if pixel_size != 4 {
+            return Err(PaletteError::UnsupportedFormat);
+        }
+        for pixel in data.chunks_exact_mut(4) {
+            if let Some(color) = self.colors.get(&*pixel) {
+                pixel.copy_from_slice(color);
+            }
+        }
+        Ok(recolored)
+    }
+}
+
+impl PaletteVariants {
+    /// Register the actor `name` as `base` recolored with `palette`, once the images of `base` are loaded.
+    pub fn add(&mut self, base: u64, name: &str, palette: Palette) {
+        self.pending.push(PaletteVariant {
+            base,
+            name: name.to_string(),
+            palette,
+        });
+    }
+
+    /// Whether some variants are waiting for the images of their base actor.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Recolored image of `image` with the palette `key`, if it was built.
+    pub fn get(&self, image: AssetId<Image>, key: &str) -> Option<&Handle<Image>> {
+        self.cache.get(&(image, key.to_string()))
+    }
+
+    /// Build the variants whose base images are loaded, and return the built actors. Variants which fail are
+    /// dropped and returned with the error.
+    pub(crate) fn build_pending(
+        &mut self,
+        animation2d: &mut ActorSpriteSheets,
+        images: &mut Assets<Image>,
+    ) -> (Vec<u64>, Vec<(String, PaletteError)>) {
+        let mut built = Vec::new();
+        let mut errors = Vec::new();
+        let pending = std::mem::take(&mut self.pending);
+        for variant in pending {
+            match self.build(&variant, animation2d, images) {
+                Ok(Some(actor)) => built.push(actor),
+                Ok(None) => self.pending.push(variant),
+                Err(e) => errors.push((variant.name, e)),
+            }
+        }
+        (built, errors)
+    }
+
+    fn build(
+        &mut self,
+        variant: &PaletteVariant,
+        animation2d: &mut ActorSpriteSheets,
+        images: &mut Assets<Image>,
+    ) -> Result<Option<u64>, PaletteError> {
+        // Actions of the base and of its parents, the closest first
+        let mut actions: HashMap<u64, &AngleSpriteSheets> = HashMap::new();
+        for actor in animation2d.lineage(variant.base) {
+            for (action, sheets) in animation2d.get(&actor).into_iter().flatten() {
+                actions.entry(*action).or_insert(sheets);
+            }
+        }
+        if actions.is_empty() {
+            return Ok(None);
+        }
+
+        let sources = actions
+            .values()
+            .flat_map(|sheets| sheets.values())
+            .flat_map(|sheet| sheet.image.iter().chain(&sheet.images));
+        let mut keys = Vec::new();
+        for source in sources {
+            let key = (source.id(), variant.palette.key.clone());
+            if !self.cache.contains_key(&key) {
+                let Some(image) = images.get(source) else {
+                    return Ok(None);
+                };
+                let recolored = variant.palette.recolor(image)?;
+                self.cache.insert(key.clone(), images.add(recolored));
+            }
+            keys.push(key);
+        }
+
+        let mut variant_sheets = Vec::new();
+        for (action, sheets) in actions {
+            for (angle, sheet) in sheets.iter() {
+                let recolored = |image: &Handle<Image>| self.get(image.id(), &variant.palette.key).cloned();
+                let image = sheet.image.as_ref().and_then(recolored);
+                let images = sheet.images.iter().filter_map(recolored).collect();
+                let sheet = SpriteSheet {
+                    image,
+                    images,
+                    ..sheet.clone()
+                };
+                variant_sheets.push((action, *angle, sheet));
+            }
+        }
+
+        // Added like other spritesheets, so a variant which was removed is shown again
+        let id = animation2d.register_name(&variant.name);
+        for (action, angle, sheet) in variant_sheets {
+            animation2d.add_sprite_sheet(id, action, angle, sheet);
+        }
+        self.built.insert(id, keys);
+        Ok(Some(id))
+    }
+
+    /// Drop the recolored images of variants whose actor is removed from `ActorSpriteSheets`, so they can be freed.
+    pub(crate) fn evict_removed(&mut self, animation2d: &ActorSpriteSheets) {
+        let count = self.built.len();
+        self.built.retain(|actor, _| animation2d.contains_key(actor));
+        if self.built.len() == count {
+            return;
+        }
+        let used: HashSet<_> = self.built.values().flatten().collect();
+        let pending = &self.pending;
+        self.cache
+            .retain(|key, _| used.contains(key) || pending.iter().any(|variant| variant.palette.key == key.1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{
+        Angle,
+        get_act_id,
+    };
+
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+    const RED: [u8; 4] = [160, 40, 40, 255];
+
+    /// Goblin whose image is the white pixel of `Image::default`, with a pending red variant.
+    fn goblin(images: &mut Assets<Image>) -> (ActorSpriteSheets, PaletteVariants, Handle<Image>) {
+        let mut animation2d = ActorSpriteSheets::default();
+        let (goblin, idle) = (animation2d.register_name("goblin"), animation2d.register_name("idle"));
+        let image = images.add(Image::default());
+        let sheet = SpriteSheet {
+            image: Some(image.clone()),
+            ..Default::default()
+        };
+        animation2d.add_sprite_sheet(goblin, idle, Angle::Front, sheet);
+        animation2d.take_changed();
+
+        let mut palettes = PaletteVariants::default();
+        palettes.add(goblin, "goblin_red", Palette::new("red", [(WHITE, RED)]));
+        (animation2d, palettes, image)
+    }
+
+    #[test]
+    fn build_marks_the_variant() {
+        let mut images = Assets::default();
+        let (mut animation2d, mut palettes, image) = goblin(&mut images);
+        let (built, errors) = palettes.build_pending(&mut animation2d, &mut images);
+        let red = animation2d.register_name("goblin_red");
+        assert!(errors.is_empty());
+        assert_eq!(built, [red]);
+        assert_eq!(animation2d.take_changed(), HashSet::from([red]));
+
+        let recolored = palettes.get(image.id(), "red").unwrap();
+        assert_eq!(images.get(recolored).unwrap().data.as_deref(), Some(&RED[..]));
+        let (sheet, _) = animation2d
+            .get_sprite_sheet(red, get_act_id("idle"), Angle::Front)
+            .unwrap();
+        assert_eq!(sheet.image.as_ref(), Some(recolored));
+        assert!(!palettes.is_pending());
+    }
+
+    #[test]
+    fn removed_variant_is_evicted_and_can_be_built_again() {
+        let mut images = Assets::default();
+        let (mut animation2d, mut palettes, image) = goblin(&mut images);
+        palettes.build_pending(&mut animation2d, &mut images);
+        let (red, idle) = (
+            animation2d.register_name("goblin_red"),
+            animation2d.register_name("idle"),
+        );
+
+        animation2d.remove_actor(red);
+        palettes.evict_removed(&animation2d);
+        assert!(palettes.get(image.id(), "red").is_none());
+
+        let goblin = animation2d.register_name("goblin");
+        palettes.add(goblin, "goblin_red", Palette::new("red", [(WHITE, RED)]));
+        palettes.build_pending(&mut animation2d, &mut images);
+        assert!(!animation2d.is_removed(red, idle));
+        assert!(palettes.get(image.id(), "red").is_some());
+    }
+
+    #[test]
+    fn cache_is_kept_while_a_variant_uses_it() {
+        let mut images = Assets::default();
+        let (mut animation2d, mut palettes, image) = goblin(&mut images);
+        let goblin = animation2d.register_name("goblin");
+        palettes.add(goblin, "goblin_red_too", Palette::new("red", [(WHITE, RED)]));
+        palettes.build_pending(&mut animation2d, &mut images);
+
+        animation2d.remove_actor(get_act_id("goblin_red"));
+        palettes.evict_removed(&animation2d);
+        assert!(palettes.get(image.id(), "red").is_some());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::component::{
+    ActorSpriteSheets,
+    SpriteSheet,
+};
+use crate::folder::{
+    SpriteSheetFolder,
+    SpriteSheetFolders,
+};
+use crate::loader::{
+    SpriteSheetFile,
+    SpriteSheetFiles,
+};
+use bevy::asset::{
+    AssetServer,
+    LoadState,
+    RecursiveDependencyLoadState,
+    UntypedAssetId,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::Res;
+
+/// Loading state of the images and layouts of sprite sheets
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SheetsLoadState {
+    Loading,
+    Loaded,
+    /// Some of the images or layouts failed to load, they will never be shown
+    Failed,
+}
+
+impl SheetsLoadState {
+    fn of(asset_server: &AssetServer, id: UntypedAssetId) -> Self {
+        match asset_server.get_load_state(id) {
+            // Assets added directly to `Assets` are not tracked by the asset server
+            None | Some(LoadState::Loaded) => SheetsLoadState::Loaded,
+            Some(LoadState::Failed(_)) => SheetsLoadState::Failed,
+            Some(_) => SheetsLoadState::Loading,
+        }
+    }
+
+    /// Failed if any is failed, else loading if any is loading.
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (SheetsLoadState::Failed, _) | (_, SheetsLoadState::Failed) => SheetsLoadState::Failed,
+            (SheetsLoadState::Loading, _) | (_, SheetsLoadState::Loading) => SheetsLoadState::Loading,
+            _ => SheetsLoadState::Loaded,
+        }
+    }
+}
+
+impl ActorSpriteSheets {
+    /// Loading state of every image and layout of an actor. An actor which is not registered is `Loaded`.
+    pub fn actor_load_state(&self, actor: u64, asset_server: &AssetServer) -> SheetsLoadState {
+        self.get(&actor)
+            .into_iter()
+            .flat_map(|actions| actions.values())
+            .flat_map(|angles| angles.values())
+            .map(|sheet| sheet_load_state(sheet, asset_server))
+            .fold(SheetsLoadState::Loaded, SheetsLoadState::merge)
+    }
+
+    /// Loading state of every image and layout of every actor.
+    pub fn load_state(&self, asset_server: &AssetServer) -> SheetsLoadState {
+        self.keys()
+            .map(|actor| self.actor_load_state(*actor, asset_server))
+            .fold(SheetsLoadState::Loaded, SheetsLoadState::merge)
+    }
+}
+
+fn sheet_load_state(sheet: &SpriteSheet, asset_server: &AssetServer) -> SheetsLoadState {
+    let image = sheet
+        .image
+        .iter()
+        .chain(&sheet.images)
+        .map(|image| image.id().untyped());
+    let layout = sheet.layout.iter().map(|layout| layout.id().untyped());
+    image
+        .chain(layout)
+        .map(|id| SheetsLoadState::of(asset_server, id))
+        .fold(SheetsLoadState::Loaded, SheetsLoadState::merge)
+}
+
+/// Readiness of the registered sprite sheets, to wait on in a loading screen.
+///
+/// Folders in `SpriteSheetFolders` and files in `SpriteSheetFiles` count as loading until they are registered, or as
+/// failed if they or their images fail to load. Other
+/// sprite sheet files (`LoadedSpriteSheets`) are registered once loaded with their images, so they are only seen after
+/// that.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::SpriteSheetsReadiness;
+///
+/// #[derive(States, Clone, Debug, Hash, Eq, PartialEq)]
+/// enum GameState {
+///     Loading,
+///     Playing,
+/// }
+///
+/// fn wait_loading(readiness: SpriteSheetsReadiness, mut next_state: ResMut<NextState<GameState>>) {
+///     if readiness.is_loaded() {
+///         next_state.set(GameState::Playing);
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct SpriteSheetsReadiness<'w> {
+    asset_server: Res<'w, AssetServer>,
+    animation2d: Res<'w, ActorSpriteSheets>,
+    folders: Res<'w, SpriteSheetFolders>,
+    files: Res<'w, SpriteSheetFiles>,
+}
+
+impl SpriteSheetsReadiness<'_> {
+    /// Loading state of every registered sprite sheet, folder and file.
+    pub fn load_state(&self) -> SheetsLoadState {
+        let folders = self
+            .folders
+            .iter()
+            .map(|folder| self.folder_load_state(folder))
+            .fold(SheetsLoadState::Loaded, SheetsLoadState::merge);
+        self.files
+            .iter()
+            .map(|file| self.file_load_state(file))
+            .fold(folders, SheetsLoadState::merge)
+            .merge(self.animation2d.load_state(&self.asset_server))
+    }
+
+    /// Loading state of a folder until it is registered, after which its sprite sheets are checked instead.
+    fn folder_load_state(&self, folder: &SpriteSheetFolder) -> SheetsLoadState {
+        if folder.is_registered() {
+            return SheetsLoadState::Loaded;
+        }
+        self.unregistered_load_state(folder.folder.id().untyped())
+    }
+
+    /// Loading state of a file until it is registered, after which its sprite sheets are checked instead.
+    fn file_load_state(&self, file: &SpriteSheetFile) -> SheetsLoadState {
+        if file.is_registered() {
+            return SheetsLoadState::Loaded;
+        }
+        self.unregistered_load_state(file.handle.id().untyped())
+    }
+
+    /// Failed if the asset or one of its dependencies failed to load, else loading until it is registered.
+    fn unregistered_load_state(&self, id: UntypedAssetId) -> SheetsLoadState {
+        let failed = matches!(self.asset_server.get_load_state(id), Some(LoadState::Failed(_)))
+            || matches!(
+                self.asset_server.get_recursive_dependency_load_state(id),
+                Some(RecursiveDependencyLoadState::Failed(_))
+            );
+        if failed { SheetsLoadState::Failed } else { SheetsLoadState::Loading }
+    }
+
+    /// Loading state of the sprite sheets of an actor.
+    pub fn actor_load_state(&self, actor: u64) -> SheetsLoadState {
+        self.animation2d.actor_load_state(actor, &self.asset_server)
+    }
+
+    /// Whether every registered sprite sheet, folder and file is loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.load_state() == SheetsLoadState::Loaded
+    }
+
+    /// Whether the sprite sheets of an actor are registered and loaded.
+    pub fn is_actor_loaded(&self, actor: u64) -> bool {
+        self.animation2d.contains_key(&actor) && self.actor_load_state(actor) == SheetsLoadState::Loaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::LoadedSpriteSheets;
+    use bevy::asset::{
+        AssetApp,
+        AssetPlugin,
+        Handle,
+    };
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::{
+        App,
+        MinimalPlugins,
+    };
+    use std::time::Duration;
+
+    fn app() -> App {
+        let mut app = App::new();
+        let dir = std::env::temp_dir().join(format!("bevy_2dviewangle_readiness_{}", std::process::id()));
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: dir.to_string_lossy().into_owned(),
+                ..Default::default()
+            },
+        ))
+        .init_asset::<LoadedSpriteSheets>()
+        .init_resource::<ActorSpriteSheets>()
+        .init_resource::<SpriteSheetFolders>()
+        .init_resource::<SpriteSheetFiles>();
+        app
+    }
+
+    /// Update the app until the readiness is not loading anymore.
+    fn settled_load_state(app: &mut App) -> SheetsLoadState {
+        for _ in 0..500 {
+            app.update();
+            let state = app
+                .world_mut()
+                .run_system_once(|readiness: SpriteSheetsReadiness| readiness.load_state())
+                .unwrap();
+            if state != SheetsLoadState::Loading {
+                return state;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        SheetsLoadState::Loading
+    }
+
+    #[test]
+    fn missing_file_fails() {
+        let mut app = app();
+        let handle: Handle<LoadedSpriteSheets> = app.world().resource::<AssetServer>().load("missing.sheets.ron");
+        app.world_mut().resource_mut::<SpriteSheetFiles>().add(handle);
+        assert_eq!(settled_load_state(&mut app), SheetsLoadState::Failed);
+    }
+
+    #[test]
+    fn missing_folder_fails() {
+        let mut app = app();
+        let folder = app.world().resource::<AssetServer>().load_folder("missing");
+        app.world_mut().resource_mut::<SpriteSheetFolders>().add(folder, None);
+        assert_eq!(settled_load_state(&mut app), SheetsLoadState::Failed);
+    }
+}
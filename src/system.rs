@@ -1,28 +1,60 @@
+use crate::atlas::AtlasPacking;
 use crate::component::{
+    ANY_ID,
+    ActorSheetsLoaded,
     ActorSpriteSheets,
-    Angle,
-    AngleSpriteSheets,
     LastFrame,
+    LodCamera,
     NextFrame,
     Notification,
-    SpriteSheet,
+    RemovedSheetPolicy,
     View2dActor,
     ViewChanged,
-    get_act_id,
 };
-use bevy::asset::Assets;
+use crate::folder::{
+    SpriteSheetFolder,
+    SpriteSheetFolders,
+};
+use crate::loader::{
+    LoadedSpriteSheets,
+    SpriteSheetFiles,
+};
+use crate::names::ViewNames;
+use crate::palette::PaletteVariants;
+use crate::readiness::SheetsLoadState;
+use bevy::asset::{
+    AssetEvent,
+    AssetServer,
+    Assets,
+    Handle,
+    LoadedFolder,
+};
 use bevy::ecs::observer::On;
-use bevy::image::TextureAtlas;
+use bevy::image::{
+    Image,
+    TextureAtlas,
+};
+use bevy::log::warn;
+use bevy::platform::collections::{
+    HashMap,
+    HashSet,
+};
 use bevy::prelude::{
     Commands,
+    DetectChanges,
+    DetectChangesMut,
     Entity,
+    GlobalTransform,
+    Local,
     MessageReader,
     MessageWriter,
     Query,
     Res,
+    ResMut,
     Sprite,
     TextureAtlasLayout,
     Time,
+    With,
 };
 
 /// Check on `ViewChanged` event and change to corresponding spritesheet.
@@ -32,70 +64,35 @@ pub(crate) fn view_changed_event(
     mut events: MessageReader<ViewChanged>,
     mut sprites: Query<(&mut View2dActor, &mut Sprite)>,
     animation2d: Res<ActorSpriteSheets>,
+    atlases: Res<Assets<TextureAtlasLayout>>,
+    removed_policy: Res<RemovedSheetPolicy>,
+    mut commands: Commands,
 ) {
     for event in events.read() {
         if let Ok((mut view, mut sprite)) = sprites.get_mut(event.entity) {
-            let mut action = view.action;
-            let mut actor = view.actor;
-            let any = get_act_id("any");
-
-            let mut viewsprite = None;
-            for _actor in [&view.actor, &any] {
-                if let Some(actor_val) = animation2d.get(_actor) {
-                    for _action in [&view.action, &any] {
-                        if let Some(action_val) = actor_val.get(_action) {
-                            viewsprite = action_val.get(&view.angle);
-                            actor = *_actor;
-                            action = *_action;
-                            break;
-                        }
-                    }
+            let Some((viewsprite, flipped)) =
+                animation2d.get_sprite_sheet_lod(view.actor, view.action, view.angle, view.lod)
+            else {
+                // The actor or action it shows has been removed
+                if sprite.image != Handle::default() && animation2d.is_removed(view.actor, view.action) {
+                    apply_removed_policy(&removed_policy, event.entity, &mut sprite, &mut commands);
                 }
-            }
+                continue;
+            };
 
-            if view.flipped {
-                sprite.flip_x = false;
-                view.flipped = false;
+            if view.flipped != flipped {
+                sprite.flip_x = flipped;
+                view.flipped = flipped;
             }
 
-            // TODO: Clean code
-            if viewsprite.is_none() {
-                let Some(actor_val) = animation2d.get(&actor) else {
-                    continue;
-                };
-                let Some(action_val) = actor_val.get(&action) else {
-                    continue;
-                };
-                viewsprite = get_opposite_view(action_val, view.angle);
-                if viewsprite.is_some() {
-                    sprite.flip_x = true;
-                    view.flipped = true;
-                }
-            }
-            if viewsprite.is_none() {
-                let Some(actor_val) = animation2d.get(&actor) else {
-                    continue;
-                };
-                let Some(action_val) = actor_val.get(&action) else {
-                    continue;
-                };
-                viewsprite = action_val.get(&Angle::Any);
-                if viewsprite.is_none() {
-                    viewsprite = get_opposite_view(&animation2d[&actor][&action], Angle::Any);
-                    if viewsprite.is_some() {
-                        sprite.flip_x = true;
-                        view.flipped = true;
-                    } else {
-                        continue;
-                    }
+            if !viewsprite.images.is_empty() {
+                // One image per frame
+                view.frame = view.frame.min(viewsprite.image_frame_count().saturating_sub(1));
+                if let Some(frame_image) = viewsprite.image_at(view.frame) {
+                    sprite.image = frame_image.clone();
                 }
-            }
-
-            let Some(viewsprite) = viewsprite else {
-                continue;
-            };
-
-            if let Some(sprite_image) = viewsprite.image.as_ref() {
+                sprite.texture_atlas = None;
+            } else if let Some(sprite_image) = viewsprite.image.as_ref() {
                 sprite.image = sprite_image.clone();
                 if let Some(atlas) = &mut sprite.texture_atlas {
                     if let Some(view_atlas) = &viewsprite.layout {
@@ -108,22 +105,278 @@ pub(crate) fn view_changed_event(
                     });
                 }
             }
+
+            if let Some(atlas) = &mut sprite.texture_atlas {
+                if !viewsprite.frames.is_empty() {
+                    view.frame = view.frame.min(viewsprite.frames.len() - 1);
+                    atlas.index = viewsprite.atlas_index(view.frame);
+                }
+
+                // The layout may have fewer frames after it is reloaded
+                if let Some(layout) = atlases.get(&atlas.layout)
+                    && atlas.index >= layout.textures.len()
+                {
+                    atlas.index = layout.textures.len().saturating_sub(1);
+                    if viewsprite.frames.is_empty() {
+                        view.frame = atlas.index;
+                    }
+                }
+            }
+
+            let frame = view.frame;
+            if let Some(duration) = viewsprite.durations.get(frame)
+                && let Some(timer) = view.animation_timer.as_mut()
+            {
+                timer.set_duration(*duration);
+            }
+        }
+    }
+}
+
+fn apply_removed_policy(policy: &RemovedSheetPolicy, entity: Entity, sprite: &mut Sprite, commands: &mut Commands) {
+    match policy {
+        RemovedSheetPolicy::Warn => {
+            warn!("Spritesheet of entity {entity} has been removed");
+            sprite.image = Handle::default();
+            sprite.texture_atlas = None;
+        }
+        RemovedSheetPolicy::Placeholder(image) => {
+            if sprite.image != *image {
+                sprite.image = image.clone();
+                sprite.texture_atlas = None;
+            }
+        }
+        RemovedSheetPolicy::Despawn => {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Copy the names of the actor and action ids into `View2dActor::names`, for `Debug` and inspectors.
+pub(crate) fn update_view_names(animation2d: Res<ActorSpriteSheets>, mut actors: Query<&mut View2dActor>) {
+    let all = animation2d.is_changed();
+    for mut view in &mut actors {
+        if !all && !view.is_changed() {
+            continue;
+        }
+        let (actor, action) = (animation2d.name(view.actor), animation2d.name(view.action));
+        if view.names.actor.as_deref() != actor || view.names.action.as_deref() != action {
+            // Only shown, so systems which react to changed actors don't run again
+            view.bypass_change_detection().names = ViewNames {
+                actor: actor.map(str::to_string),
+                action: action.map(str::to_string),
+            };
+        }
+    }
+}
+
+/// Pick the level of detail of actors from their distance to the `LodCamera`.
+pub(crate) fn select_lod(
+    cameras: Query<&GlobalTransform, With<LodCamera>>,
+    mut actors: Query<(Entity, &mut View2dActor, &GlobalTransform)>,
+    animation2d: Res<ActorSpriteSheets>,
+    mut view_changed: MessageWriter<ViewChanged>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    if !animation2d.has_lods() {
+        return;
+    }
+
+    for (entity, mut view, transform) in &mut actors {
+        let distance = camera.translation().distance(transform.translation());
+        let lod = animation2d.lod_level(view.actor, view.action, distance);
+        if view.lod != lod {
+            view.lod = lod;
+            view_changed.write(ViewChanged { entity });
+        }
+    }
+}
+
+/// Send `ViewChanged` to entities whose spritesheets are changed or reloaded, so they don't keep stale handles.
+pub(crate) fn refresh_changed_sheets(
+    mut animation2d: ResMut<ActorSpriteSheets>,
+    mut image_events: MessageReader<AssetEvent<Image>>,
+    mut layout_events: MessageReader<AssetEvent<TextureAtlasLayout>>,
+    actors: Query<(Entity, &View2dActor, &Sprite)>,
+    mut view_changed: MessageWriter<ViewChanged>,
+) {
+    let modified_images: HashSet<_> = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let modified_layouts: HashSet<_> = layout_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    let mut changed_actors = HashSet::new();
+    let mut changed_all = false;
+    if animation2d.is_changed() {
+        changed_actors = animation2d.bypass_change_detection().take_changed();
+        // Changed directly on the map, or the fallback actor is changed
+        changed_all = changed_actors.is_empty() || changed_actors.contains(&ANY_ID);
+    }
+
+    if !changed_all && changed_actors.is_empty() && modified_images.is_empty() && modified_layouts.is_empty() {
+        return;
+    }
+
+    for (entity, view, sprite) in &actors {
+        let is_changed = changed_all
+            || animation2d
+                .lineage(view.actor)
+                .any(|actor| changed_actors.contains(&actor))
+            || modified_images.contains(&sprite.image.id())
+            || sprite
+                .texture_atlas
+                .as_ref()
+                .is_some_and(|atlas| modified_layouts.contains(&atlas.layout.id()));
+        if is_changed {
+            view_changed.write(ViewChanged { entity });
+        }
+    }
+}
+
+/// Store sprite sheets into `ActorSpriteSheets` once their file and images are loaded.
+pub(crate) fn register_loaded_sheets(
+    mut events: MessageReader<AssetEvent<LoadedSpriteSheets>>,
+    loaded: Res<Assets<LoadedSpriteSheets>>,
+    mut animation2d: ResMut<ActorSpriteSheets>,
+    mut files: ResMut<SpriteSheetFiles>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(sheets) = loaded.get(*id)
+        {
+            animation2d.load_sprite_sheets(sheets);
+            files.registered(*id);
+        }
+    }
+}
+
+/// Store images of sprite sheet folders into `ActorSpriteSheets` once the folders are loaded.
+pub(crate) fn register_sheet_folders(
+    mut folders: ResMut<SpriteSheetFolders>,
+    loaded: Res<Assets<LoadedFolder>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut animation2d: ResMut<ActorSpriteSheets>,
+) {
+    if folders.iter().all(SpriteSheetFolder::is_registered) {
+        return;
+    }
+
+    for folder in folders.iter_mut().filter(|folder| !folder.registered) {
+        if let Some(path) = folder.folder.path().cloned()
+            && let Some(loaded_folder) = loaded.get(&folder.folder)
+        {
+            animation2d.load_folder(&path, loaded_folder, folder.grid.as_ref(), &mut layouts);
+            folder.registered = true;
+        }
+    }
+}
+
+/// Build the layouts of spritesheets with an `auto_grid` once their images are loaded.
+pub(crate) fn build_auto_grids(
+    mut animation2d: ResMut<ActorSpriteSheets>,
+    images: Res<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let built = animation2d
+        .bypass_change_detection()
+        .build_auto_grids(&images, &mut layouts);
+    for actor in built {
+        animation2d.mark_changed(actor);
+    }
+}
+
+/// Send `ActorSheetsLoaded` when the images and layouts of an actor finish loading.
+pub(crate) fn notify_loaded_actors(
+    animation2d: Res<ActorSpriteSheets>,
+    asset_server: Res<AssetServer>,
+    mut states: Local<HashMap<u64, SheetsLoadState>>,
+    mut loaded: MessageWriter<ActorSheetsLoaded>,
+) {
+    if !animation2d.is_changed() && !states.values().any(|state| *state == SheetsLoadState::Loading) {
+        return;
+    }
+
+    for &actor in animation2d.keys() {
+        let state = animation2d.actor_load_state(actor, &asset_server);
+        if states.insert(actor, state) == Some(state) {
+            continue;
+        }
+        match state {
+            SheetsLoadState::Loaded => {
+                loaded.write(ActorSheetsLoaded { actor });
+            }
+            SheetsLoadState::Failed => warn!(
+                "Some sprite sheets of actor {} failed to load",
+                animation2d.act_name(actor)
+            ),
+            SheetsLoadState::Loading => {}
+        }
+    }
+    states.retain(|actor, _| animation2d.contains_key(actor));
+}
+
+/// Pack the images of actors requested in `AtlasPacking` once they are loaded.
+pub(crate) fn pack_actor_atlases(
+    mut packing: ResMut<AtlasPacking>,
+    mut animation2d: ResMut<ActorSpriteSheets>,
+    mut images: ResMut<Assets<Image>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let pending: Vec<u64> = packing.pending().collect();
+    for actor in pending {
+        // Only a packed actor changes the spritesheets
+        match animation2d
+            .bypass_change_detection()
+            .pack_actor(actor, &packing, &mut images, &mut layouts)
+        {
+            Ok(false) => {}
+            Ok(true) => {
+                animation2d.set_changed();
+                packing.done(actor);
+            }
+            Err(e) => {
+                warn!(
+                    "Could not pack sprite sheets of actor {}: {e}",
+                    animation2d.act_name(actor)
+                );
+                packing.done(actor);
+            }
         }
     }
 }
 
-fn get_opposite_view(texture: &AngleSpriteSheets, direction: Angle) -> Option<&SpriteSheet> {
-    match direction {
-        Angle::Left => texture.get(&Angle::Right),
-        Angle::Right => texture.get(&Angle::Left),
-        Angle::FrontLeft => texture.get(&Angle::FrontRight),
-        Angle::FrontRight => texture.get(&Angle::FrontLeft),
-        Angle::BackLeft => texture.get(&Angle::BackRight),
-        Angle::BackRight => texture.get(&Angle::BackLeft),
-        _ => None,
+/// Build palette variants once the images of their base actor are loaded.
+pub(crate) fn build_palette_variants(
+    mut palettes: ResMut<PaletteVariants>,
+    mut animation2d: ResMut<ActorSpriteSheets>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let (built, errors) = palettes.build_pending(animation2d.bypass_change_detection(), &mut images);
+    for actor in built {
+        animation2d.mark_changed(actor);
+    }
+    for (variant, e) in errors {
+        warn!("Could not build palette variant {variant}: {e}");
     }
 }
 
+/// Free the recolored images of palette variants whose actor is removed.
+pub(crate) fn evict_palette_variants(mut palettes: ResMut<PaletteVariants>, animation2d: Res<ActorSpriteSheets>) {
+    palettes.bypass_change_detection().evict_removed(&animation2d);
+}
+
 pub(crate) fn animated_timer(time: Res<Time>, mut query: Query<(&mut View2dActor, Entity)>, mut commands: Commands) {
     for (mut actor, entity) in &mut query {
         if let Some(ref mut animation_timer) = actor.animation_timer {
@@ -139,31 +392,85 @@ pub(crate) fn animating(
     trigger: On<NextFrame>,
     mut commands: Commands,
     atlases: Res<Assets<TextureAtlasLayout>>,
+    animation2d: Res<ActorSpriteSheets>,
     mut query: Query<(&mut View2dActor, &mut Sprite)>,
     mut event: MessageWriter<ViewChanged>,
 ) {
-    if let Ok((mut actor, mut sprite)) = query.get_mut(trigger.entity)
-        && let Some(atlas) = &mut sprite.texture_atlas
-        && let Some(layout) = atlases.get(&atlas.layout)
-    {
+    if let Ok((mut actor, mut sprite)) = query.get_mut(trigger.entity) {
+        let sheet = animation2d
+            .get_sprite_sheet_lod(actor.actor, actor.action, actor.angle, actor.lod)
+            .map(|(sheet, _)| sheet);
+        let atlas_frames = sprite.texture_atlas.as_ref().and_then(|atlas| {
+            let layout = atlases.get(&atlas.layout)?;
+            Some((atlas.index, layout.textures.len()))
+        });
+        let (frame, frame_count) = match (sheet, atlas_frames) {
+            (Some(sheet), _) if !sheet.images.is_empty() => (actor.frame, sheet.image_frame_count()),
+            (Some(sheet), Some(_)) if !sheet.frames.is_empty() => (actor.frame, sheet.frames.len()),
+            (_, Some(atlas_frames)) => atlas_frames,
+            _ => return,
+        };
+        if frame_count == 0 {
+            return;
+        }
+        let is_last_frame = frame + 1 >= frame_count;
+
         for notify in &actor.notify {
             match *notify {
                 Notification::LastFrame => {
-                    if atlas.index == layout.textures.len() - 1 {
+                    if is_last_frame {
                         commands.trigger(LastFrame { entity: trigger.entity });
                     }
                 }
             }
         }
 
-        if atlas.index == layout.textures.len() - 1
-            && let Some(next_action) = actor.next_action.first()
-        {
+        if is_last_frame && let Some(next_action) = actor.next_action.first() {
             actor.action = *next_action;
             event.write(ViewChanged { entity: trigger.entity });
             actor.next_action.remove(0);
         }
 
-        atlas.index = (atlas.index + 1) % layout.textures.len();
+        actor.frame = (frame + 1) % frame_count;
+        if let Some(frame_image) = sheet.and_then(|sheet| sheet.image_at(actor.frame)) {
+            sprite.image = frame_image.clone();
+        } else if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = sheet.map_or(actor.frame, |sheet| sheet.atlas_index(actor.frame));
+        }
+
+        let frame = actor.frame;
+        if let Some(duration) = sheet.and_then(|sheet| sheet.durations.get(frame))
+            && let Some(timer) = actor.animation_timer.as_mut()
+        {
+            timer.set_duration(*duration);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::World;
+
+    #[test]
+    fn view_names_follow_the_registry() {
+        let mut world = World::new();
+        let mut animation2d = ActorSpriteSheets::default();
+        let (frog, idle) = (animation2d.register_name("frog"), animation2d.register_name("idle"));
+        world.insert_resource(animation2d);
+        let entity = world
+            .spawn(View2dActor {
+                actor: frog,
+                action: idle,
+                ..Default::default()
+            })
+            .id();
+
+        world.run_system_once(update_view_names).unwrap();
+        let view = world.get::<View2dActor>(entity).unwrap();
+        assert_eq!(view.names.actor.as_deref(), Some("frog"));
+        assert_eq!(view.names.action.as_deref(), Some("idle"));
+        assert!(format!("{view:?}").contains("\"frog\""));
     }
 }
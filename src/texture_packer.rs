@@ -0,0 +1,435 @@
+use crate::atlas::blank_image;
+use crate::component::{
+    Angle,
+    SpriteSheet,
+};
+use crate::loader::{
+    LoadedSpriteSheets,
+    NamedSpriteSheet,
+};
+use bevy::asset::io::Reader;
+use bevy::asset::{
+    AssetLoader,
+    LoadContext,
+};
+use bevy::image::Image;
+use bevy::log::warn;
+use bevy::math::{
+    URect,
+    UVec2,
+    UVec3,
+};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::TextureAtlasLayout;
+use bevy::reflect::TypePath;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// Load a packed sprite sheet described by the JSON data of TexturePacker or free-tex-packer (JSON hash or JSON
+/// array format).
+///
+/// Frame names are mapped to actor, action, angle and frame number: `frog/idle/left_03.png` or
+/// `frog/idle/left/03.png` is the 4th frame of the actor `frog`, action `idle`, angle `left`. If the angle is left out
+/// (`frog/idle/03.png`), the frame is registered at the `any` angle. Frames of a sprite sheet are played in order of
+/// their numbers. Frames named otherwise stay in the atlas but are skipped with a warning, unless no frame is named
+/// this way.
+///
+/// Trimmed and rotated frames are restored to their original size and orientation in a new image.
+///
+/// Example:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::LoadedSpriteSheets;
+///
+/// fn setup(asset_server: Res<AssetServer>) {
+///     let sheets: Handle<LoadedSpriteSheets> = asset_server.load("characters.atlas.json");
+/// }
+/// ```
+#[derive(Default, TypePath)]
+pub struct TexturePackerLoader;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct TexturePackerLoaderSettings {
+    /// Actor name of every frame. If set, frame names only contain action, angle and frame number, such as
+    /// `idle/left_03.png`.
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TexturePackerLoaderError {
+    #[error("could not read packed atlas file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse packed atlas json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid image path `{0}`")]
+    ImagePath(String),
+    #[error("could not load atlas image: {0}")]
+    Image(String),
+    #[error("no frame is named as actor/action/angle_frame, such as `{0}`")]
+    FrameName(String),
+    #[error("frame `{0}` is outside of the atlas image")]
+    FrameOutOfImage(String),
+}
+
+#[derive(Deserialize)]
+struct PackedFile {
+    frames: PackedFrames,
+    meta: PackedMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PackedFrames {
+    Array(Vec<PackedFrame>),
+    /// Keeps the order of the file with the `preserve_order` feature of `serde_json`
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackedFrame {
+    #[serde(default)]
+    filename: String,
+    frame: PackedRect,
+    #[serde(default)]
+    rotated: bool,
+    #[serde(default)]
+    trimmed: bool,
+    sprite_source_size: Option<PackedRect>,
+    source_size: Option<PackedSize>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct PackedRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct PackedSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct PackedMeta {
+    image: String,
+    size: PackedSize,
+}
+
+impl PackedFrame {
+    /// Region of the frame in the atlas image. Rotated frames are stored 90° clockwise, so width and height swap.
+    fn atlas_rect(&self) -> URect {
+        let PackedRect { x, y, w, h } = self.frame;
+        if self.rotated { URect::new(x, y, x + h, y + w) } else { URect::new(x, y, x + w, y + h) }
+    }
+
+    fn needs_restore(&self) -> bool {
+        self.rotated || self.trimmed
+    }
+
+    /// Size of the frame before trimming.
+    fn source_size(&self) -> UVec2 {
+        self.source_size.map_or(UVec2::new(self.frame.w, self.frame.h), |size| {
+            UVec2::new(size.w, size.h)
+        })
+    }
+}
+
+impl AssetLoader for TexturePackerLoader {
+    type Asset = LoadedSpriteSheets;
+    type Settings = TexturePackerLoaderSettings;
+    type Error = TexturePackerLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: PackedFile = serde_json::from_slice(&bytes)?;
+
+        let frames = match file.frames {
+            PackedFrames::Array(frames) => frames,
+            PackedFrames::Hash(frames) => frames
+                .into_iter()
+                .map(|(name, frame)| {
+                    let mut frame: PackedFrame = serde_json::from_value(frame)?;
+                    frame.filename = name;
+                    Ok(frame)
+                })
+                .collect::<Result<_, serde_json::Error>>()?,
+        };
+
+        let image_path = load_context
+            .path()
+            .resolve_embed(&file.meta.image)
+            .map_err(|_| TexturePackerLoaderError::ImagePath(file.meta.image.clone()))?;
+
+        let (image, layout) = if frames.iter().any(PackedFrame::needs_restore) {
+            let atlas = load_context
+                .loader()
+                .immediate()
+                .load::<Image>(image_path)
+                .await
+                .map_err(|e| TexturePackerLoaderError::Image(e.to_string()))?
+                .take();
+            let (image, layout) = restore_frames(&atlas, &frames)?;
+            (load_context.add_labeled_asset("image".to_string(), image), layout)
+        } else {
+            let mut layout = TextureAtlasLayout::new_empty(UVec2::new(file.meta.size.w, file.meta.size.h));
+            for frame in &frames {
+                layout.add_texture(frame.atlas_rect());
+            }
+            (load_context.load(image_path), layout)
+        };
+        let layout = load_context.add_labeled_asset("layout".to_string(), layout);
+
+        let groups = group_frames(
+            frames.iter().map(|frame| frame.filename.as_str()),
+            settings.actor.as_deref(),
+            &load_context.path().to_string(),
+        )?;
+
+        let sheets = groups
+            .into_iter()
+            .map(|(actor, action, angle, frames)| NamedSpriteSheet {
+                actor,
+                action,
+                angle,
+                sheet: SpriteSheet {
+                    image: Some(image.clone()),
+                    layout: Some(layout.clone()),
+                    frames,
+                    ..Default::default()
+                },
+            })
+            .collect();
+
+        Ok(LoadedSpriteSheets {
+            sheets,
+            ..Default::default()
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["atlas.json"]
+    }
+}
+
+/// Atlas indices of the frames of each actor, action and angle, in order of their numbers. Frames which are not named
+/// by the convention are skipped with a warning, unless none is.
+fn group_frames<'a>(
+    names: impl Iterator<Item = &'a str>,
+    actor: Option<&str>,
+    file: &str,
+) -> Result<Vec<(String, String, Angle, Vec<usize>)>, TexturePackerLoaderError> {
+    let mut groups: Vec<(String, String, Angle, Vec<(Option<u32>, usize)>)> = Vec::new();
+    let mut group_indices = HashMap::new();
+    let mut first_name = None;
+    for (index, name) in names.enumerate() {
+        first_name.get_or_insert(name);
+        let Some((actor, action, angle, number)) = parse_frame_name(name, actor) else {
+            warn!("Frame `{name}` of {file} is not named as actor/action/angle_frame, it is skipped");
+            continue;
+        };
+        let group = *group_indices
+            .entry((actor.clone(), action.clone(), angle))
+            .or_insert_with(|| {
+                groups.push((actor, action, angle, Vec::new()));
+                groups.len() - 1
+            });
+        groups[group].3.push((number, index));
+    }
+
+    if groups.is_empty()
+        && let Some(name) = first_name
+    {
+        return Err(TexturePackerLoaderError::FrameName(name.to_string()));
+    }
+    Ok(groups
+        .into_iter()
+        .map(|(actor, action, angle, mut indices)| {
+            indices.sort_by_key(|(number, _)| *number);
+            (
+                actor,
+                action,
+                angle,
+                indices.into_iter().map(|(_, index)| index).collect(),
+            )
+        })
+        .collect())
+}
+
+/// Map a frame name like `frog/idle/left_03.png` to actor, action, angle and frame number.
+fn parse_frame_name(name: &str, actor: Option<&str>) -> Option<(String, String, Angle, Option<u32>)> {
+    let name = match name.rsplit_once('.') {
+        Some((stem, extension)) if !extension.contains('/') => stem,
+        _ => name,
+    };
+    let mut parts: Vec<&str> = name.split('/').filter(|part| !part.is_empty()).collect();
+
+    let last = parts.pop()?;
+    let (base, number) = split_frame_number(last);
+    let (angle_part, number) = match (base.is_empty(), parts.pop()) {
+        // `left/03`
+        (true, Some(angle_part)) => (angle_part, number),
+        (true, None) => return None,
+        (false, previous) => {
+            parts.extend(previous);
+            (base, number)
+        }
+    };
+    let angle = match angle_part.parse() {
+        Ok(angle) => angle,
+        Err(_) => {
+            parts.push(angle_part);
+            Angle::Any
+        }
+    };
+
+    let (actor, action) = match actor {
+        Some(actor) => (actor.to_string(), parts.join("/")),
+        None if parts.len() >= 2 => (parts[0].to_string(), parts[1..].join("/")),
+        None => return None,
+    };
+    if action.is_empty() {
+        return None;
+    }
+    Some((actor, action, angle, number))
+}
+
+/// Split `left_03` into `("left", Some(3))`.
+fn split_frame_number(name: &str) -> (&str, Option<u32>) {
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = name[base.len()..].parse().ok();
+    (base.trim_end_matches(['_', '-', ' ']), number)
+}
+
+/// Copy every frame into a new image at its original size and orientation.
+fn restore_frames(
+    atlas: &Image,
+    frames: &[PackedFrame],
+) -> Result<(Image, TextureAtlasLayout), TexturePackerLoaderError> {
+    let max_width = frames
+        .iter()
+        .map(|frame| frame.source_size().x)
+        .max()
+        .unwrap_or_default()
+        .max(atlas.width());
+
+    // Place frames in rows
+    let mut rects = Vec::with_capacity(frames.len());
+    let mut cursor = UVec2::ZERO;
+    let mut row_height = 0;
+    let mut size = UVec2::ZERO;
+    for frame in frames {
+        let frame_size = frame.source_size();
+        if cursor.x + frame_size.x > max_width {
+            cursor = UVec2::new(0, cursor.y + row_height);
+            row_height = 0;
+        }
+        rects.push(URect::from_corners(cursor, cursor + frame_size));
+        cursor.x += frame_size.x;
+        row_height = row_height.max(frame_size.y);
+        size = size.max(cursor + UVec2::new(0, row_height));
+    }
+
+    let mut image = blank_image(atlas, size);
+
+    let mut layout = TextureAtlasLayout::new_empty(size);
+    for (frame, rect) in frames.iter().zip(rects) {
+        let offset = frame
+            .sprite_source_size
+            .filter(|_| frame.trimmed)
+            .map_or(UVec2::ZERO, |source| UVec2::new(source.x, source.y));
+        let atlas_rect = frame.atlas_rect();
+        for y in 0..frame.frame.h {
+            for x in 0..frame.frame.w {
+                // Rotated frames are turned 90° clockwise in the atlas
+                let from = if frame.rotated {
+                    UVec3::new(atlas_rect.min.x + frame.frame.h - 1 - y, atlas_rect.min.y + x, 0)
+                } else {
+                    UVec3::new(atlas_rect.min.x + x, atlas_rect.min.y + y, 0)
+                };
+                let to = (rect.min + offset + UVec2::new(x, y)).extend(0);
+                let pixel = atlas
+                    .pixel_bytes(from)
+                    .ok_or_else(|| TexturePackerLoaderError::FrameOutOfImage(frame.filename.clone()))?;
+                if let Some(target) = image.pixel_bytes_mut(to) {
+                    target.copy_from_slice(pixel);
+                }
+            }
+        }
+        layout.add_texture(rect);
+    }
+
+    Ok((image, layout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_name_with_angle_and_number() {
+        assert_eq!(
+            parse_frame_name("frog/idle/left_03.png", None),
+            Some(("frog".to_string(), "idle".to_string(), Angle::Left, Some(3)))
+        );
+        assert_eq!(
+            parse_frame_name("frog/idle/left/03.png", None),
+            Some(("frog".to_string(), "idle".to_string(), Angle::Left, Some(3)))
+        );
+    }
+
+    #[test]
+    fn frame_name_without_angle_is_any() {
+        assert_eq!(
+            parse_frame_name("frog/idle/03.png", None),
+            Some(("frog".to_string(), "idle".to_string(), Angle::Any, Some(3)))
+        );
+    }
+
+    #[test]
+    fn frame_name_with_given_actor() {
+        assert_eq!(
+            parse_frame_name("idle/back_1", Some("frog")),
+            Some(("frog".to_string(), "idle".to_string(), Angle::Back, Some(1)))
+        );
+    }
+
+    #[test]
+    fn frame_name_without_action() {
+        assert_eq!(parse_frame_name("frog_03.png", None), None);
+        assert_eq!(parse_frame_name("03.png", Some("frog")), None);
+    }
+
+    #[test]
+    fn frame_number() {
+        assert_eq!(split_frame_number("left_03"), ("left", Some(3)));
+        assert_eq!(split_frame_number("walk2-10"), ("walk2", Some(10)));
+        assert_eq!(split_frame_number("left"), ("left", None));
+        assert_eq!(split_frame_number("12"), ("", Some(12)));
+    }
+
+    #[test]
+    fn stray_frames_are_skipped() {
+        let names = ["frog/idle/left_01.png", "frog_idle_03", "frog/idle/left_00.png"];
+        let groups = group_frames(names.into_iter(), None, "frog.atlas.json").unwrap();
+        assert_eq!(
+            groups,
+            [("frog".to_string(), "idle".to_string(), Angle::Left, vec![2, 0])]
+        );
+        assert!(matches!(
+            group_frames(["frog_idle_03"].into_iter(), None, "frog.atlas.json"),
+            Err(TexturePackerLoaderError::FrameName(name)) if name == "frog_idle_03"
+        ));
+    }
+}
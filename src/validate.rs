@@ -0,0 +1,399 @@
+use crate::component::{
+    ActorSpriteSheets,
+    Angle,
+    AngleSpriteSheets,
+};
+use bevy::asset::Assets;
+use bevy::image::Image;
+use bevy::log::warn;
+use bevy::math::{
+    URect,
+    UVec2,
+};
+use bevy::platform::collections::HashSet;
+use bevy::prelude::{
+    Res,
+    TextureAtlasLayout,
+};
+use std::fmt;
+
+/// Problems found in `ActorSpriteSheets` by `ActorSpriteSheets::validate`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<SpriteSheetIssue>,
+}
+
+/// A problem of the spritesheet of an actor's action at an angle
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteSheetIssue {
+    pub actor: u64,
+    pub action: u64,
+    pub angle: Angle,
+    pub problem: SpriteSheetProblem,
+}
+
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum SpriteSheetProblem {
+    /// Another action of the actor has this angle, but this action can't show it, even flipped or with `Any`
+    #[error("angle is missing, while other actions of the actor have it")]
+    MissingAngle,
+    #[error("layout has no image")]
+    MissingImage,
+    #[error("has {frames} frames, while angle {expected_angle:?} of the same action has {expected}")]
+    FrameCountMismatch {
+        frames: usize,
+        expected: usize,
+        expected_angle: Angle,
+    },
+    #[error("frame {frame} uses atlas index {index}, but the layout has {len} cells")]
+    FrameOutOfLayout { frame: usize, index: usize, len: usize },
+    #[error("layout cell {index} {rect:?} is outside of the image of size {image_size}")]
+    CellOutsideImage {
+        index: usize,
+        rect: URect,
+        image_size: UVec2,
+    },
+}
+
+impl fmt::Display for SpriteSheetIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "actor {} action {} angle {:?}: {}",
+            self.actor, self.action, self.angle, self.problem
+        )
+    }
+}
+
+impl ValidationReport {
+    /// Whether no problem is found.
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Problems of one actor.
+    pub fn actor(&self, actor: u64) -> impl Iterator<Item = &SpriteSheetIssue> {
+        self.issues.iter().filter(move |issue| issue.actor == actor)
+    }
+
+    /// Problems of one actor's action.
+    pub fn action(&self, actor: u64, action: u64) -> impl Iterator<Item = &SpriteSheetIssue> {
+        self.actor(actor).filter(move |issue| issue.action == action)
+    }
+}
+
+impl ActorSpriteSheets {
+    /// Check every spritesheet for mistakes which otherwise fail silently.
+    ///
+    /// Checks which need the size of an image or a layout are skipped while it is not loaded.
+    pub fn validate(&self, images: &Assets<Image>, layouts: &Assets<TextureAtlasLayout>) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        for (&actor, actions) in self.iter() {
+            let actor_angles: HashSet<Angle> = actions
+                .values()
+                .flat_map(|sheets| sheets.keys().copied())
+                .filter(|angle| *angle != Angle::Any)
+                .collect();
+
+            for (&action, sheets) in actions {
+                let mut push = |angle, problem| {
+                    report.issues.push(SpriteSheetIssue {
+                        actor,
+                        action,
+                        angle,
+                        problem,
+                    })
+                };
+
+                for angle in Angle::ALL.into_iter().filter(|angle| actor_angles.contains(angle)) {
+                    if !can_show(sheets, angle) {
+                        push(angle, SpriteSheetProblem::MissingAngle);
+                    }
+                }
+
+                let mut expected: Option<(Angle, usize)> = None;
+                for angle in Angle::ALL {
+                    let Some(sheet) = sheets.get(&angle) else {
+                        continue;
+                    };
+                    // `Any` often holds only the layout shared by the other angles, which are checked after they are
+                    // filled from it
+                    if angle != Angle::Any && sheet.layout.is_some() && sheet.image.is_none() {
+                        push(angle, SpriteSheetProblem::MissingImage);
+                    }
+                    let Some(layout) = sheet.layout.as_ref().and_then(|layout| layouts.get(layout)) else {
+                        continue;
+                    };
+
+                    let len = layout.textures.len();
+                    for (frame, &index) in sheet.frames.iter().enumerate() {
+                        if index >= len {
+                            push(angle, SpriteSheetProblem::FrameOutOfLayout { frame, index, len });
+                        }
+                    }
+
+                    if let Some(image) = sheet.image.as_ref().and_then(|image| images.get(image)) {
+                        let image_size = image.size();
+                        for (index, rect) in layout.textures.iter().enumerate() {
+                            if rect.max.x > image_size.x || rect.max.y > image_size.y {
+                                push(
+                                    angle,
+                                    SpriteSheetProblem::CellOutsideImage {
+                                        index,
+                                        rect: *rect,
+                                        image_size,
+                                    },
+                                );
+                            }
+                        }
+                    }
+
+                    let frames = sheet.frame_count(layout);
+                    match expected {
+                        None => expected = Some((angle, frames)),
+                        Some((expected_angle, expected)) if expected != frames => push(
+                            angle,
+                            SpriteSheetProblem::FrameCountMismatch {
+                                frames,
+                                expected,
+                                expected_angle,
+                            },
+                        ),
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Whether an angle can be shown by itself, by flipping its opposite, or by `Any`.
+fn can_show(sheets: &AngleSpriteSheets, angle: Angle) -> bool {
+    sheets.contains_key(&angle)
+        || angle.opposite().is_some_and(|opposite| sheets.contains_key(&opposite))
+        || sheets.contains_key(&Angle::Any)
+}
+
+/// Log every problem of `ActorSpriteSheets` as a warning.
+///
+/// This system is not added by the plugin. Run it once the sprite sheets are loaded, such as when leaving the loading
+/// state:
+/// ```rust
+/// use bevy::prelude::*;
+/// use bevy_2dviewangle::validate_sprite_sheets;
+///
+/// #[derive(States, Clone, Debug, Hash, Eq, PartialEq)]
+/// enum GameState {
+///     Loading,
+///     Playing,
+/// }
+///
+/// fn build(app: &mut App) {
+///     app.add_systems(OnEnter(GameState::Playing), validate_sprite_sheets);
+/// }
+/// ```
+pub fn validate_sprite_sheets(
+    animation2d: Res<ActorSpriteSheets>,
+    images: Res<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+) {
+    for issue in animation2d.validate(&images, &layouts).issues {
+        warn!(
+            "Invalid sprite sheet: actor {} action {} angle {:?}: {}",
+            animation2d.act_name(issue.actor),
+            animation2d.act_name(issue.action),
+            issue.angle,
+            issue.problem
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atlas::blank_image;
+    use crate::component::{
+        GridLayout,
+        SpriteSheet,
+        get_act_id,
+    };
+    use bevy::asset::Handle;
+
+    struct Fixture {
+        animation2d: ActorSpriteSheets,
+        images: Assets<Image>,
+        layouts: Assets<TextureAtlasLayout>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                animation2d: ActorSpriteSheets::default(),
+                images: Assets::default(),
+                layouts: Assets::default(),
+            }
+        }
+
+        fn image(&mut self, width: u32, height: u32) -> Option<Handle<Image>> {
+            Some(
+                self.images
+                    .add(blank_image(&Image::default(), UVec2::new(width, height))),
+            )
+        }
+
+        /// One column of three 16x16 cells
+        fn layout(&mut self) -> Option<Handle<TextureAtlasLayout>> {
+            let grid = GridLayout {
+                tile_size: UVec2::new(16, 16),
+                columns: 1,
+                rows: 3,
+                ..Default::default()
+            };
+            Some(self.layouts.add(grid.to_layout()))
+        }
+
+        fn add(&mut self, action: &str, angle: Angle, sheet: SpriteSheet) {
+            self.animation2d
+                .add_sprite_sheet(get_act_id("frog"), get_act_id(action), angle, sheet);
+        }
+
+        fn problems(&self, action: &str) -> Vec<(Angle, SpriteSheetProblem)> {
+            self.animation2d
+                .validate(&self.images, &self.layouts)
+                .action(get_act_id("frog"), get_act_id(action))
+                .map(|issue| (issue.angle, issue.problem.clone()))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn valid_sheets() {
+        let mut fixture = Fixture::new();
+        let layout = fixture.layout();
+        fixture.add(
+            "idle",
+            Angle::Any,
+            SpriteSheet {
+                layout,
+                ..Default::default()
+            },
+        );
+        for angle in [Angle::Front, Angle::Back, Angle::Left] {
+            let image = fixture.image(16, 48);
+            fixture.add(
+                "idle",
+                angle,
+                SpriteSheet {
+                    image,
+                    ..Default::default()
+                },
+            );
+        }
+        assert!(
+            fixture
+                .animation2d
+                .validate(&fixture.images, &fixture.layouts)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn missing_angle() {
+        let mut fixture = Fixture::new();
+        fixture.add("idle", Angle::Front, SpriteSheet::default());
+        fixture.add("idle", Angle::Left, SpriteSheet::default());
+        fixture.add("walk", Angle::Front, SpriteSheet::default());
+        assert_eq!(fixture.problems("idle"), []);
+        assert_eq!(
+            fixture.problems("walk"),
+            [(Angle::Left, SpriteSheetProblem::MissingAngle)]
+        );
+    }
+
+    #[test]
+    fn missing_image() {
+        let mut fixture = Fixture::new();
+        let layout = fixture.layout();
+        fixture.add(
+            "idle",
+            Angle::Any,
+            SpriteSheet {
+                layout,
+                ..Default::default()
+            },
+        );
+        fixture.add("idle", Angle::Front, SpriteSheet::default());
+        assert_eq!(
+            fixture.problems("idle"),
+            [(Angle::Front, SpriteSheetProblem::MissingImage)]
+        );
+    }
+
+    #[test]
+    fn frames_out_of_layout() {
+        let mut fixture = Fixture::new();
+        let (image, layout) = (fixture.image(16, 48), fixture.layout());
+        fixture.add(
+            "idle",
+            Angle::Any,
+            SpriteSheet {
+                image,
+                layout,
+                ..Default::default()
+            },
+        );
+        let sheet = SpriteSheet {
+            frames: vec![0, 3],
+            ..Default::default()
+        };
+        fixture.add("idle", Angle::Front, sheet);
+        assert_eq!(
+            fixture.problems("idle"),
+            [
+                (
+                    Angle::Front,
+                    SpriteSheetProblem::FrameOutOfLayout {
+                        frame: 1,
+                        index: 3,
+                        len: 3
+                    }
+                ),
+                (
+                    Angle::Front,
+                    SpriteSheetProblem::FrameCountMismatch {
+                        frames: 2,
+                        expected: 3,
+                        expected_angle: Angle::Any,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn cell_outside_image() {
+        let mut fixture = Fixture::new();
+        let (image, layout) = (fixture.image(16, 32), fixture.layout());
+        fixture.add(
+            "idle",
+            Angle::Front,
+            SpriteSheet {
+                image,
+                layout,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            fixture.problems("idle"),
+            [(
+                Angle::Front,
+                SpriteSheetProblem::CellOutsideImage {
+                    index: 2,
+                    rect: URect::new(0, 32, 16, 48),
+                    image_size: UVec2::new(16, 32),
+                }
+            )]
+        );
+    }
+}